@@ -1,90 +1,316 @@
-use ethabi::Contract;
-use ethers::types::Address;
-use log::{error, info};
-use reqwest::Client;
+use ethabi::Address;
 use serde_json::Value;
-use std::env;
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-
-pub async fn fetch_abi_from_arbiscan(
-    contract_address: &str,
-) -> Result<Value, Box<dyn std::error::Error>> {
-    info!(
-        "🌐 Solicitando ABI de Arbiscan para contrato: {}",
-        contract_address
-    );
-    let api_key = env::var("ARBISCAN_API_KEY").unwrap_or_default();
-
-    let url = if api_key.is_empty() {
-        format!(
-            "https://api-sepolia.arbiscan.io/api?module=contract&action=getabi&address={}",
-            contract_address
-        )
-    } else {
-        format!(
-            "https://api-sepolia.arbiscan.io/api?module=contract&action=getabi&address={}&apikey={}",
-            contract_address,
-            api_key
-        )
-    };
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info, warn};
+
+use crate::decoder::{AbiCache, CachedAbi, SOURCE_LEGACY};
+use crate::error::AppError;
+
+#[cfg(feature = "explorer")]
+pub use crate::explorer::get_or_fetch_abi;
+
+// Caché de ABIs en archivos JSON dentro de un directorio (por defecto `ABI/`),
+// uno por contrato en `<dir>/<chain_id>/<dirección con checksum>.json`
+pub struct FsAbiCache {
+    dir: PathBuf,
+}
+
+// Subdirectorio al que `migrate` aparta los archivos antiguos que no puede
+// asignar a un contrato
+const UNRESOLVED_DIR: &str = "unresolved";
+
+// Resultado de `FsAbiCache::migrate`
+#[derive(Default)]
+pub struct AbiMigration {
+    pub migrated: usize,
+    pub unresolved: Vec<PathBuf>, // Ya movidos a `<dir>/unresolved/`
+}
 
-    let client = Client::new();
-    info!("📤 Enviando solicitud a Arbiscan API");
-    let response = client.get(&url).send().await?;
-    let json: Value = response.json().await?;
-
-    if json["status"] == "1" {
-        info!("✅ ABI obtenido exitosamente de Arbiscan");
-        let abi_string = json["result"].as_str().unwrap();
-        let abi: Value = serde_json::from_str(abi_string)?;
-        Ok(abi)
-    } else {
-        let error_msg = json["message"].as_str().unwrap_or("Error desconocido");
-        error!("❌ Error al obtener ABI de Arbiscan: {}", error_msg);
-        Err(format!("Error al obtener ABI: {}", error_msg).into())
+impl FsAbiCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FsAbiCache { dir: dir.into() }
+    }
+
+    fn path_for(&self, chain_id: u64, address: &Address) -> PathBuf {
+        self.dir
+            .join(chain_id.to_string())
+            .join(format!("{}.json", checksum_address(address)))
+    }
+
+    // Mueve los archivos de versiones anteriores, guardados directamente en
+    // `<dir>/` y sin cadena, al formato actual. Los que no traen la cadena se
+    // asignan a `default_chain_id`. Los nombres abreviados (`0xddc3…edbb.json`)
+    // no identifican al contrato: se apartan a `<dir>/unresolved/` para que se
+    // vuelvan a descargar o se suban con `PUT /abis`.
+    pub fn migrate(&self, default_chain_id: u64) -> Result<AbiMigration, AppError> {
+        let mut report = AbiMigration::default();
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(AppError::Storage(e.to_string())),
+        };
+        for file in dir {
+            let path = file.map_err(|e| AppError::Storage(e.to_string()))?.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let stem_address = address_from_stem(&path);
+            let entry = match read_entry(&path, Some(default_chain_id), stem_address) {
+                Ok(Some(entry)) if stem_address.is_none_or(|address| address == entry.address) => {
+                    entry
+                }
+                Ok(_) => {
+                    report.unresolved.push(self.set_aside(&path)?);
+                    continue;
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "archivo de ABI ilegible, se aparta");
+                    report.unresolved.push(self.set_aside(&path)?);
+                    continue;
+                }
+            };
+            let chain_id = entry.chain_id.unwrap_or(default_chain_id);
+            if self.path_for(chain_id, &entry.address).exists() {
+                // Ya hay una entrada más reciente en el formato actual
+                report.unresolved.push(self.set_aside(&path)?);
+                continue;
+            }
+            self.put(&CachedAbi {
+                chain_id: Some(chain_id),
+                ..entry
+            })?;
+            fs::remove_file(&path).map_err(|e| AppError::Storage(e.to_string()))?;
+            report.migrated += 1;
+        }
+
+        if report.migrated > 0 {
+            info!(dir = %self.dir.display(), migrated = report.migrated, "caché de ABIs migrada");
+        }
+        for path in &report.unresolved {
+            warn!(path = %path.display(), "archivo de ABI antiguo sin contrato identificable; apartado");
+        }
+        Ok(report)
+    }
+
+    fn set_aside(&self, path: &Path) -> Result<PathBuf, AppError> {
+        let target_dir = self.dir.join(UNRESOLVED_DIR);
+        fs::create_dir_all(&target_dir).map_err(|e| AppError::Storage(e.to_string()))?;
+        let target = target_dir.join(path.file_name().unwrap_or_default());
+        fs::rename(path, &target).map_err(|e| AppError::Storage(e.to_string()))?;
+        Ok(target)
     }
 }
 
-pub async fn get_or_fetch_abi(
-    contract_address: &Address,
-) -> Result<(Contract, Value), Box<dyn std::error::Error>> {
-    let abi_dir = "ABI";
-    let abi_filename = format!("{}.json", contract_address);
-    let abi_path = Path::new(abi_dir).join(&abi_filename);
-
-    if !Path::new(abi_dir).exists() {
-        info!("📁 Creando directorio ABI: {}", abi_dir);
-        fs::create_dir_all(abi_dir)?;
-    }
-
-    if abi_path.exists() {
-        info!("📖 Cargando ABI desde archivo local: {}", abi_filename);
-        let abi_string = fs::read_to_string(&abi_path)?;
-        let abi: Value = serde_json::from_str(&abi_string)?;
-        let contract = Contract::load(abi.to_string().as_bytes())?;
-        info!("✅ ABI cargado exitosamente desde archivo local");
-        return Ok((contract, abi));
-    }
-
-    let address_string = format!("{:?}", contract_address);
-    info!(
-        "🌐 ABI no encontrado localmente, buscando en Arbiscan: {}",
-        address_string
-    );
-
-    match fetch_abi_from_arbiscan(&address_string).await {
-        Ok(abi) => {
-            info!("✅ ABI obtenido exitosamente de Arbiscan");
-            let abi_string = serde_json::to_string_pretty(&abi)?;
-            fs::write(&abi_path, &abi_string)?;
-            info!("💾 ABI guardado en archivo local: {}", abi_filename);
-            let contract = Contract::load(abi_string.as_bytes())?;
-            Ok((contract, abi))
+impl AbiCache for FsAbiCache {
+    fn get(&self, chain_id: u64, address: &Address) -> Result<Option<CachedAbi>, AppError> {
+        let abi_path = self.path_for(chain_id, address);
+        let entry = match read_entry(&abi_path, Some(chain_id), Some(*address)) {
+            Err(AppError::NotFound(_)) => return Ok(None),
+            result => result?,
+        };
+        debug!(path = %abi_path.display(), "ABI cargado desde la caché local");
+        Ok(entry)
+    }
+
+    fn put(&self, entry: &CachedAbi) -> Result<(), AppError> {
+        let chain_id = entry
+            .chain_id
+            .ok_or_else(|| AppError::Storage(format!("ABI de {:?} sin cadena", entry.address)))?;
+        let abi_path = self.path_for(chain_id, &entry.address);
+        if let Some(chain_dir) = abi_path.parent().filter(|dir| !dir.exists()) {
+            info!(dir = %chain_dir.display(), "creando directorio de ABIs");
+            fs::create_dir_all(chain_dir).map_err(|e| AppError::Storage(e.to_string()))?;
+        }
+        let abi_string =
+            serde_json::to_string_pretty(entry).map_err(|e| AppError::InvalidAbi(e.to_string()))?;
+        write_atomic(&abi_path, abi_string.as_bytes())?;
+        info!(path = %abi_path.display(), "ABI guardado en la caché local");
+        Ok(())
+    }
+
+    fn remove(&self, chain_id: u64, address: &Address) -> Result<bool, AppError> {
+        match fs::remove_file(self.path_for(chain_id, address)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(AppError::Storage(e.to_string())),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<CachedAbi>, AppError> {
+        let mut entries = Vec::new();
+        for chain_dir in read_dir_paths(&self.dir)? {
+            let Some(chain_id) = chain_dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<u64>().ok())
+            else {
+                if chain_dir.extension().is_some_and(|ext| ext == "json") {
+                    warn!(path = %chain_dir.display(), "archivo de ABI sin migrar, se omite");
+                }
+                continue;
+            };
+            for path in read_dir_paths(&chain_dir)? {
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                match read_entry(&path, Some(chain_id), address_from_stem(&path)) {
+                    Ok(Some(entry)) => entries.push(entry),
+                    Ok(None) => {
+                        warn!(path = %path.display(), "archivo de ABI sin dirección reconocible, se omite")
+                    }
+                    Err(e) => {
+                        warn!(path = %path.display(), error = %e, "archivo de ABI ilegible, se omite")
+                    }
+                }
+            }
         }
-        Err(e) => {
-            error!("❌ Error al obtener ABI de Arbiscan: {}", e);
-            Err(format!("No se pudo obtener el ABI: {}. Asegúrate de que el contrato esté verificado en Arbiscan Sepolia.", e).into())
+        Ok(entries)
+    }
+}
+
+// Dirección con checksum EIP-55, la forma que se usa en los nombres de archivo
+pub fn checksum_address(address: &Address) -> String {
+    let hex_address = hex::encode(address.as_bytes());
+    let hash = hex::encode(Keccak256::digest(hex_address.as_bytes()));
+    let checksummed: String = hex_address
+        .chars()
+        .zip(hash.chars())
+        .map(|(c, h)| if h >= '8' { c.to_ascii_uppercase() } else { c })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+// Rutas de un directorio; vacío si no existe
+fn read_dir_paths(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::Storage(e.to_string())),
+    };
+    entries
+        .map(|entry| {
+            entry
+                .map(|entry| entry.path())
+                .map_err(|e| AppError::Storage(e.to_string()))
+        })
+        .collect()
+}
+
+// Los archivos antiguos solo tienen la dirección en el nombre; None si está abreviada
+fn address_from_stem(path: &Path) -> Option<Address> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.parse::<Address>().ok())
+}
+
+// Formato en disco: `CachedAbi` serializado. Los archivos antiguos contienen
+// solo el array ABI; se leen como entradas "legacy" de `chain_id` con la
+// fecha de modificación del archivo (None si no se conoce su dirección).
+fn read_entry(
+    path: &Path,
+    chain_id: Option<u64>,
+    address: Option<Address>,
+) -> Result<Option<CachedAbi>, AppError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(AppError::NotFound(path.display().to_string()))
         }
+        Err(e) => return Err(AppError::Storage(e.to_string())),
+    };
+    let value: Value =
+        serde_json::from_str(&content).map_err(|e| AppError::InvalidAbi(e.to_string()))?;
+    if !value.is_array() {
+        return serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| AppError::InvalidAbi(format!("{}: {}", path.display(), e)));
+    }
+    let fetched_at = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_secs());
+    Ok(address.map(|address| CachedAbi {
+        chain_id,
+        address,
+        source: SOURCE_LEGACY.to_string(),
+        fetched_at,
+        content_hash: None,
+        contract_name: None,
+        compiler_version: None,
+        implementation: None,
+        abi: value,
+    }))
+}
+
+// Escribe en un temporal del mismo directorio y lo renombra: un lector nunca
+// ve un archivo a medio escribir y dos escrituras concurrentes no se mezclan
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), AppError> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let tmp_path = path.with_extension(format!(
+        "{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp_path, contents)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            AppError::Storage(format!("{}: {}", path.display(), e))
+        })
+}
+
+type MemoryEntries = HashMap<(u64, Address), CachedAbi>;
+
+// Caché de ABIs en memoria, útil para embeber el decoder sin disco
+#[derive(Default)]
+pub struct MemoryAbiCache {
+    entries: RwLock<MemoryEntries>,
+}
+
+impl MemoryAbiCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, MemoryEntries>, AppError> {
+        self.entries
+            .read()
+            .map_err(|_| AppError::Storage("Lock de la caché envenenado".to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, MemoryEntries>, AppError> {
+        self.entries
+            .write()
+            .map_err(|_| AppError::Storage("Lock de la caché envenenado".to_string()))
+    }
+}
+
+impl AbiCache for MemoryAbiCache {
+    fn get(&self, chain_id: u64, address: &Address) -> Result<Option<CachedAbi>, AppError> {
+        Ok(self.read()?.get(&(chain_id, *address)).cloned())
+    }
+
+    fn put(&self, entry: &CachedAbi) -> Result<(), AppError> {
+        self.write()?.insert(
+            (entry.chain_id.unwrap_or_default(), entry.address),
+            entry.clone(),
+        );
+        Ok(())
+    }
+
+    fn remove(&self, chain_id: u64, address: &Address) -> Result<bool, AppError> {
+        Ok(self.write()?.remove(&(chain_id, *address)).is_some())
+    }
+
+    fn list(&self) -> Result<Vec<CachedAbi>, AppError> {
+        Ok(self.read()?.values().cloned().collect())
     }
 }
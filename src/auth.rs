@@ -0,0 +1,249 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderMap, AUTHORIZATION};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest, ResponseError};
+use chrono::{Days, NaiveDate, Utc};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::warn;
+
+use crate::error::AppError;
+use crate::{ApiKeyConfig, AuthConfig};
+
+// Endpoints cuyo coste incluye una llamada al proveedor LLM
+const LLM_ENDPOINTS: [&str; 1] = ["/analysis"];
+
+// Prefijo de la administración de la caché de ABIs; todo salvo GET exige admin
+const ADMIN_PREFIX: &str = "/abis";
+
+// Presupuesto contra el que se cuenta una petición
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budget {
+    Decode,
+    Llm,
+}
+
+impl Budget {
+    pub fn for_endpoint(pattern: &str) -> Self {
+        if LLM_ENDPOINTS.contains(&pattern) {
+            Budget::Llm
+        } else {
+            Budget::Decode
+        }
+    }
+}
+
+// Nombre de la API key autenticada; queda en las extensiones de la petición
+#[derive(Clone)]
+pub struct ApiKeyName(pub String);
+
+// Presente en las extensiones si la clave autenticada tiene `admin = true`
+#[derive(Clone)]
+pub struct AdminKey;
+
+// Estado de un presupuesto (token bucket + contador diario) de una clave
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+    day: NaiveDate,
+    used_today: u64,
+}
+
+// Valida API keys y aplica los límites configurados. Los contadores viven en
+// memoria, por proceso.
+pub struct Authenticator {
+    config: AuthConfig,
+    keys: HashMap<String, usize>, // key_hash (minúsculas) -> índice en config.keys
+    buckets: Mutex<HashMap<(usize, Budget), BucketState>>,
+}
+
+impl Authenticator {
+    pub fn new(config: AuthConfig) -> Self {
+        let keys = config
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.key_hash.to_lowercase(), i))
+            .collect();
+        Authenticator {
+            config,
+            keys,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Devuelve la clave autenticada, o None si la ruta no la requiere
+    pub fn authorize(
+        &self,
+        headers: &HeaderMap,
+        method: &Method,
+        path: &str,
+        endpoint: &str,
+    ) -> Result<Option<&ApiKeyConfig>, AppError> {
+        if !self.config.enabled || self.config.public_paths.iter().any(|p| p == path) {
+            return Ok(None);
+        }
+
+        let presented = presented_key(headers).ok_or_else(|| {
+            AppError::Unauthorized(
+                "usa la cabecera `Authorization: Bearer <clave>` o `X-API-Key`".to_string(),
+            )
+        })?;
+        let index = *self
+            .keys
+            .get(&hash_key(presented))
+            .ok_or_else(|| AppError::Unauthorized("clave desconocida".to_string()))?;
+        let key = &self.config.keys[index];
+        if endpoint.starts_with(ADMIN_PREFIX) && method != Method::GET && !key.admin {
+            return Err(AppError::Forbidden(format!(
+                "'{}' no es una clave de administración",
+                key.name
+            )));
+        }
+
+        let budget = Budget::for_endpoint(endpoint);
+        self.consume(index, key, budget)?;
+        Ok(Some(key))
+    }
+
+    fn consume(&self, index: usize, key: &ApiKeyConfig, budget: Budget) -> Result<(), AppError> {
+        let (limit, budget_name) = match budget {
+            Budget::Decode => (&key.decode, "decode"),
+            Budget::Llm => (&key.llm, "llm"),
+        };
+        let capacity = limit.burst.unwrap_or(limit.per_minute) as f64;
+        let per_second = limit.per_minute as f64 / 60.0;
+        let now = Instant::now();
+        let today = Utc::now().date_naive();
+
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|_| AppError::Internal("Lock de límites envenenado".to_string()))?;
+        let state = buckets
+            .entry((index, budget))
+            .or_insert_with(|| BucketState {
+                tokens: capacity,
+                refilled_at: now,
+                day: today,
+                used_today: 0,
+            });
+
+        state.tokens = (state.tokens
+            + now.duration_since(state.refilled_at).as_secs_f64() * per_second)
+            .min(capacity);
+        state.refilled_at = now;
+        if state.day != today {
+            state.day = today;
+            state.used_today = 0;
+        }
+
+        if let Some(quota) = limit.daily_quota {
+            if state.used_today >= quota {
+                return Err(AppError::QuotaExceeded(
+                    format!(
+                        "'{}' agotó sus {} peticiones diarias de {}",
+                        key.name, quota, budget_name
+                    ),
+                    seconds_until_midnight(),
+                ));
+            }
+        }
+        if state.tokens < 1.0 {
+            let retry_after = ((1.0 - state.tokens) / per_second).ceil() as u64;
+            return Err(AppError::RateLimited(
+                format!(
+                    "'{}' superó {} peticiones/minuto de {}",
+                    key.name, limit.per_minute, budget_name
+                ),
+                retry_after.max(1),
+            ));
+        }
+
+        state.tokens -= 1.0;
+        state.used_today += 1;
+        Ok(())
+    }
+}
+
+// Middleware: rechaza con 401/429 antes de llegar al handler. Sin
+// `Authenticator` en app_data no hace nada.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let result = match req.app_data::<web::Data<Authenticator>>() {
+        Some(auth) => {
+            let endpoint = req
+                .match_pattern()
+                .unwrap_or_else(|| req.path().to_string());
+            auth.authorize(req.headers(), req.method(), req.path(), &endpoint)
+        }
+        None => Ok(None),
+    };
+
+    match result {
+        Ok(key) => {
+            if let Some(key) = key {
+                let (name, admin) = (key.name.clone(), key.admin);
+                req.extensions_mut().insert(ApiKeyName(name));
+                if admin {
+                    req.extensions_mut().insert(AdminKey);
+                }
+            }
+            next.call(req).await.map(|res| res.map_into_left_body())
+        }
+        Err(e) => {
+            warn!(code = e.code(), error = %e, "petición rechazada");
+            let response = e.error_response();
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+// Para operaciones de administración que la ruta no distingue, como guardar
+// en caché el ABI de una petición a /decode. Sin autenticación se permite.
+pub fn require_admin(req: &HttpRequest, config: &AuthConfig) -> Result<(), AppError> {
+    if !config.enabled || req.extensions().contains::<AdminKey>() {
+        return Ok(());
+    }
+    Err(AppError::Forbidden(
+        match req.extensions().get::<ApiKeyName>() {
+            Some(ApiKeyName(name)) => format!("'{}' no es una clave de administración", name),
+            None => "se necesita una clave de administración".to_string(),
+        },
+    ))
+}
+
+// Hash con el que se guardan las claves en la configuración
+pub fn hash_key(key: &str) -> String {
+    format!("0x{}", hex::encode(Keccak256::digest(key.as_bytes())))
+}
+
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer
+        .or_else(|| {
+            headers
+                .get("x-api-key")
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+fn seconds_until_midnight() -> u64 {
+    let now = Utc::now();
+    now.date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+        .map(|midnight| (midnight - now.naive_utc()).num_seconds().max(1) as u64)
+        .unwrap_or(1)
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+use crate::decoder::{CachedAbi, Decoder};
+use crate::error::AppError;
+
+// Identifica el archivo y la versión de su formato. Una instancia importa
+// bundles de versiones iguales o anteriores a la suya.
+pub const BUNDLE_FORMAT: &str = "rdt-abi-bundle";
+pub const BUNDLE_VERSION: u32 = 1;
+
+// Caché de ABIs y base de firmas en un único archivo JSON, para llevarlos a
+// entornos sin acceso al explorador
+#[derive(Serialize, Deserialize)]
+pub struct AbiBundle {
+    pub manifest: BundleManifest,
+    pub abis: Vec<CachedAbi>,
+    pub signatures: BTreeMap<String, Vec<String>>, // Selector o topic en hex -> firmas
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: String,
+    pub version: u32,
+    pub created_at: u64, // Segundos unix
+    pub generator: String,
+    pub entries: Vec<BundleEntry>,
+    pub signature_count: usize,
+    pub content_hash: String, // keccak256 de `abis` y `signatures` serializados
+}
+
+// Un ABI del bundle; `hash` es `CachedAbi::hash` y se comprueba al importar
+#[derive(Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub chain_id: u64,
+    pub address: String,
+    pub hash: String,
+}
+
+// Resultado de `import_bundle`
+#[derive(Default)]
+pub struct BundleImport {
+    pub imported: usize,
+    pub skipped: usize, // Ya estaban en caché y no se pidió sobrescribir
+    pub signatures: usize,
+}
+
+// Exporta todas las entradas de la caché y la base de firmas del decoder
+pub fn export_bundle(decoder: &Decoder) -> Result<AbiBundle, AppError> {
+    let mut abis: Vec<CachedAbi> = decoder
+        .cached_abis()?
+        .into_iter()
+        .filter(|cached| cached.chain_id.is_some())
+        .collect();
+    abis.sort_by_key(|cached| (cached.chain_id, cached.address));
+    let signatures = decoder.signature_entries()?;
+
+    let entries = abis
+        .iter()
+        .map(|cached| BundleEntry {
+            chain_id: cached.chain_id.unwrap_or_default(),
+            address: format!("{:?}", cached.address),
+            hash: cached.hash(),
+        })
+        .collect();
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        generator: format!("rust_decompile_test {}", env!("CARGO_PKG_VERSION")),
+        entries,
+        signature_count: signatures.len(),
+        content_hash: content_hash(&abis, &signatures)?,
+    };
+    info!(
+        abis = abis.len(),
+        signatures = signatures.len(),
+        "bundle de ABIs exportado"
+    );
+    Ok(AbiBundle {
+        manifest,
+        abis,
+        signatures,
+    })
+}
+
+// Comprueba el manifiesto completo antes de escribir nada: un bundle
+// incompleto o modificado se rechaza entero
+pub fn verify_bundle(bundle: &AbiBundle) -> Result<(), AppError> {
+    let manifest = &bundle.manifest;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(invalid(format!(
+            "formato desconocido '{}'",
+            manifest.format
+        )));
+    }
+    if manifest.version > BUNDLE_VERSION {
+        return Err(invalid(format!(
+            "versión {} no soportada (máximo {})",
+            manifest.version, BUNDLE_VERSION
+        )));
+    }
+    if manifest.content_hash != content_hash(&bundle.abis, &bundle.signatures)? {
+        return Err(invalid(
+            "el contenido no coincide con content_hash del manifiesto".to_string(),
+        ));
+    }
+    if manifest.entries.len() != bundle.abis.len() {
+        return Err(invalid(format!(
+            "el manifiesto declara {} ABIs y el bundle contiene {}",
+            manifest.entries.len(),
+            bundle.abis.len()
+        )));
+    }
+    for (entry, cached) in manifest.entries.iter().zip(&bundle.abis) {
+        let same_contract = Some(entry.chain_id) == cached.chain_id
+            && entry.address.parse().ok() == Some(cached.address);
+        if !same_contract || entry.hash != cached.hash() {
+            return Err(invalid(format!(
+                "el ABI de {} (cadena {}) no coincide con su hash",
+                entry.address, entry.chain_id
+            )));
+        }
+    }
+    for selector in bundle.signatures.keys() {
+        let valid = matches!(selector.len(), 8 | 64)
+            && selector
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c));
+        if !valid {
+            return Err(invalid(format!("selector inválido '{}'", selector)));
+        }
+    }
+    Ok(())
+}
+
+// Importa un bundle verificado. Las entradas que ya están en caché se
+// conservan salvo con `overwrite`.
+pub fn import_bundle(
+    decoder: &Decoder,
+    bundle: AbiBundle,
+    overwrite: bool,
+) -> Result<BundleImport, AppError> {
+    verify_bundle(&bundle)?;
+    let mut report = BundleImport::default();
+    for cached in bundle.abis {
+        let chain_id = cached.chain_id.unwrap_or_default();
+        if !overwrite && decoder.cached_abi(chain_id, &cached.address)?.is_some() {
+            report.skipped += 1;
+            continue;
+        }
+        decoder.import_abi(cached)?;
+        report.imported += 1;
+    }
+    for (selector, signatures) in bundle.signatures {
+        decoder.import_signatures(&selector, signatures)?;
+        report.signatures += 1;
+    }
+    if report.skipped > 0 {
+        warn!(
+            skipped = report.skipped,
+            "ABIs del bundle ya presentes en caché; no se sobrescriben"
+        );
+    }
+    info!(
+        imported = report.imported,
+        signatures = report.signatures,
+        "bundle de ABIs importado"
+    );
+    Ok(report)
+}
+
+fn content_hash(
+    abis: &[CachedAbi],
+    signatures: &BTreeMap<String, Vec<String>>,
+) -> Result<String, AppError> {
+    let content =
+        serde_json::to_vec(&(abis, signatures)).map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(format!("0x{}", hex::encode(Keccak256::digest(&content))))
+}
+
+fn invalid(reason: String) -> AppError {
+    AppError::InvalidRequest(format!("bundle inválido: {}", reason))
+}
@@ -0,0 +1,612 @@
+use clap::{Args, Subcommand, ValueEnum};
+use ethabi::Address;
+use ethabi::{Contract, Function, Param};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use rust_decompile_test::abi::get_or_fetch_abi;
+use rust_decompile_test::auth::hash_key;
+use rust_decompile_test::bundle::{export_bundle, import_bundle, AbiBundle};
+use rust_decompile_test::config::load_prompt_config;
+use rust_decompile_test::decode::{
+    canonical_signature, decode_function, decode_with_candidates, format_token,
+};
+use rust_decompile_test::decoder::{self, Decoder};
+use rust_decompile_test::disasm::disassemble_hex;
+use rust_decompile_test::error::AppError;
+use rust_decompile_test::human_abi::parse_abi_str;
+use rust_decompile_test::llm::{self, AnalysisCache};
+use rust_decompile_test::store::{abi_cache_from_config, SignatureDb};
+use rust_decompile_test::strict::check_encoding;
+use rust_decompile_test::summary::{
+    annotate_arguments, summarize, token_amounts, uses_token_metadata,
+};
+use rust_decompile_test::token::TokenResolver;
+use rust_decompile_test::upstream::Upstreams;
+use rust_decompile_test::{
+    AbiImportResponse, AnalysisResponse, AppConfig, DecodeResponse, ErrorResponse, InlineAbi,
+    TokenAmount, TokenMetadata,
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Inicia el servidor web (comportamiento por defecto)
+    Serve,
+    /// Decodifica datos de llamada de un contrato
+    Decode {
+        #[command(flatten)]
+        call: CallArgs,
+        /// Recodifica los argumentos e informa de relleno sucio, offsets no
+        /// canónicos y bytes sobrantes
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Decodifica y evalúa el riesgo de una llamada con el LLM
+    Analyze(CallArgs),
+    /// Obtiene el ABI de un contrato (caché local o explorador) y lo guarda
+    FetchAbi {
+        /// Dirección del contrato
+        #[arg(long)]
+        address: String,
+        /// Cadena del registro (por defecto default_chain_id)
+        #[arg(long)]
+        chain_id: Option<u64>,
+    },
+    /// Lista los selectores de funciones, eventos y errores de un ABI
+    Selectors {
+        /// Archivo con el ABI (JSON, firmas legibles o interfaz de Solidity)
+        abi: PathBuf,
+    },
+    /// Desensambla bytecode EVM
+    Disasm {
+        /// Bytecode en hex, o @archivo con el bytecode en hex
+        bytecode: String,
+    },
+    /// Exporta la caché de ABIs y la base de firmas como un bundle JSON
+    ExportAbis {
+        /// Archivo de salida (por defecto la salida estándar)
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Importa un bundle generado con export-abis
+    ImportAbis {
+        /// Archivo del bundle
+        bundle: PathBuf,
+        /// Sustituye los ABIs que ya estén en caché
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// Calcula el hash de una API key para la sección [auth] de la configuración
+    HashKey {
+        /// API key en claro
+        key: String,
+    },
+}
+
+#[derive(Args)]
+pub struct CallArgs {
+    /// Dirección del contrato (opcional si se indica --abi o --signature)
+    #[arg(long, required_unless_present_any = ["abi", "signature"])]
+    address: Option<String>,
+    /// Datos de llamada en hex (con o sin 0x)
+    #[arg(long)]
+    calldata: String,
+    /// Archivo con el ABI (JSON, firmas legibles o interfaz de Solidity); evita la
+    /// búsqueda en caché/explorador
+    #[arg(long)]
+    abi: Option<PathBuf>,
+    /// Firma de la función llamada, p. ej. "transfer(address,uint256)"; evita
+    /// la búsqueda en caché/explorador
+    #[arg(long, conflicts_with = "abi")]
+    signature: Option<String>,
+    /// Cadena del registro (por defecto default_chain_id)
+    #[arg(long)]
+    chain_id: Option<u64>,
+}
+
+// Ejecuta un subcomando y devuelve el código de salida del proceso
+pub async fn run(command: Command, output: OutputFormat, config: &AppConfig) -> i32 {
+    let result = match command {
+        Command::Serve => unreachable!("serve se gestiona en main"),
+        Command::Decode { call, strict } => decode(&call, strict, output, config).await,
+        Command::Analyze(args) => analyze(&args, output, config).await,
+        Command::FetchAbi { address, chain_id } => {
+            fetch_abi(&address, chain_id, output, config).await
+        }
+        Command::Selectors { abi } => selectors(&abi, output),
+        Command::Disasm { bytecode } => disasm(&bytecode, output),
+        Command::ExportAbis { file } => export_abis(file.as_deref(), output, config),
+        Command::ImportAbis { bundle, overwrite } => {
+            import_abis(&bundle, overwrite, output, config)
+        }
+        Command::HashKey { key } => {
+            let key_hash = hash_key(&key);
+            match output {
+                OutputFormat::Json => print_json(&json!({ "key_hash": key_hash })),
+                OutputFormat::Text => println!("{}", key_hash),
+            }
+            Ok(())
+        }
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            match output {
+                OutputFormat::Json => print_json(&ErrorResponse {
+                    status: "error".to_string(),
+                    code: e.code().to_string(),
+                    message: e.to_string(),
+                    details: None,
+                }),
+                OutputFormat::Text => eprintln!("❌ [{}] {}", e.code(), e),
+            }
+            1
+        }
+    }
+}
+
+async fn decode(
+    args: &CallArgs,
+    strict: bool,
+    output: OutputFormat,
+    config: &AppConfig,
+) -> Result<(), AppError> {
+    let (contract, _abi) = load_contract(args, config).await?;
+    let (decoded, candidates) = decode_with_candidates(&contract, &args.calldata);
+    let (function, tokens) = decoded?;
+    let name = function.name.clone();
+    let findings = strict
+        .then(|| check_encoding(function, &tokens, &args.calldata))
+        .transpose()?;
+    let token = token_metadata(args, function, config).await;
+    let summary = summarize(function, &tokens, token.as_ref());
+    let amounts = token_amounts(function, &tokens, token.as_ref());
+
+    match output {
+        OutputFormat::Json => print_json(&DecodeResponse {
+            status: "success".to_string(),
+            function_name: Some(name),
+            arguments: Some(tokens.iter().map(|arg| format!("{:?}", arg)).collect()),
+            summary,
+            token,
+            amounts: Some(amounts).filter(|amounts| !amounts.is_empty()),
+            message: None,
+            details: None,
+            code: None,
+            abi: None,
+            candidates,
+            findings,
+        }),
+        OutputFormat::Text => {
+            print_call(&name, Some(function), &tokens);
+            print_token(token.as_ref(), &amounts);
+            if let Some(summary) = summary {
+                println!();
+                println!("Resumen:   {}", summary);
+            }
+            if let Some(candidates) = candidates {
+                let chosen = signature_of(&function.name, &function.inputs);
+                println!();
+                println!("Otras funciones con el mismo selector:");
+                for candidate in candidates.iter().filter(|c| c.signature != chosen) {
+                    match &candidate.error {
+                        None => println!("  ✓ {} (también decodifica)", candidate.signature),
+                        Some(error) => println!("  ✗ {}: {}", candidate.signature, error),
+                    }
+                }
+            }
+            match findings.as_deref() {
+                Some([]) => {
+                    println!();
+                    println!("Codificación canónica ✓");
+                }
+                Some(findings) => {
+                    println!();
+                    println!("Codificación no canónica:");
+                    for finding in findings {
+                        let path = finding.path.as_deref().unwrap_or("-");
+                        println!(
+                            "  ⚠ [{:?}] byte {} ({}): {}",
+                            finding.kind, finding.offset, path, finding.detail
+                        );
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn analyze(
+    args: &CallArgs,
+    output: OutputFormat,
+    config: &AppConfig,
+) -> Result<(), AppError> {
+    llm::api_key(config)?;
+    let (contract, _abi) = load_contract(args, config).await?;
+    let (function, tokens) = decode_function(&contract, &args.calldata)?;
+    let name = function.name.clone();
+    let arguments: Vec<String> = tokens.iter().map(|arg| format!("{:?}", arg)).collect();
+    let token = token_metadata(args, function, config).await;
+    let summary = summarize(function, &tokens, token.as_ref());
+    let amounts = token_amounts(function, &tokens, token.as_ref());
+
+    let prompt_config = load_prompt_config(config.storage.prompt_config.as_deref())?;
+    let contract_address = args.address.clone().unwrap_or_default();
+    let prompt = llm::build_prompt(
+        config,
+        &prompt_config,
+        &contract_address,
+        &name,
+        &annotate_arguments(&arguments, &amounts),
+        summary.as_deref(),
+    );
+    let analysis_cache = AnalysisCache::from_config(config)?;
+    let verdict = match analysis_cache.get(&prompt).await {
+        Some(verdict) => verdict,
+        None => {
+            let upstreams = Upstreams::from_config(config)?;
+            let verdict =
+                llm::request_verdict(&upstreams.llm, config, &prompt_config, &prompt).await?;
+            analysis_cache.put(&prompt, &verdict).await;
+            verdict
+        }
+    };
+
+    match output {
+        OutputFormat::Json => print_json(&AnalysisResponse {
+            status: "success".to_string(),
+            function_name: Some(name),
+            arguments: Some(arguments),
+            risk_level: verdict.risk_level,
+            explanation: verdict.explanation,
+            token,
+            amounts: Some(amounts).filter(|amounts| !amounts.is_empty()),
+            message: Some("Análisis de riesgo completado".to_string()),
+            details: None,
+            code: None,
+        }),
+        OutputFormat::Text => {
+            print_call(&name, Some(function), &tokens);
+            print_token(token.as_ref(), &amounts);
+            if let Some(summary) = summary {
+                println!();
+                println!("Resumen:   {}", summary);
+            }
+            println!();
+            println!(
+                "Nivel de riesgo: {}",
+                verdict.risk_level.as_deref().unwrap_or("desconocido")
+            );
+            println!("Modelo:          {}", prompt.model);
+            if let Some(explanation) = verdict.explanation {
+                println!();
+                println!("{}", explanation);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_abi(
+    address: &str,
+    chain_id: Option<u64>,
+    output: OutputFormat,
+    config: &AppConfig,
+) -> Result<(), AppError> {
+    let contract_address = parse_address(address)?;
+    let chain = config.chain(chain_id)?;
+    let (contract, abi) = get_or_fetch_abi(config, chain, &contract_address).await?;
+
+    match output {
+        OutputFormat::Json => print_json(&abi),
+        OutputFormat::Text => {
+            println!("ABI de {:?} ({})", contract_address, chain.name);
+            print_selectors(&contract);
+        }
+    }
+    Ok(())
+}
+
+fn export_abis(
+    path: Option<&Path>,
+    output: OutputFormat,
+    config: &AppConfig,
+) -> Result<(), AppError> {
+    let bundle = export_bundle(&offline_decoder(config)?)?;
+    let Some(path) = path else {
+        // Sin --file el bundle ocupa la salida estándar
+        print_json(&bundle);
+        return Ok(());
+    };
+    let json =
+        serde_json::to_string_pretty(&bundle).map_err(|e| AppError::Internal(e.to_string()))?;
+    fs::write(path, json).map_err(|e| AppError::Storage(format!("{}: {}", path.display(), e)))?;
+
+    match output {
+        OutputFormat::Json => print_json(&json!({
+            "path": path,
+            "abis": bundle.abis.len(),
+            "signatures": bundle.signatures.len(),
+            "content_hash": bundle.manifest.content_hash,
+        })),
+        OutputFormat::Text => println!(
+            "Bundle escrito en {}: {} ABIs, {} firmas",
+            path.display(),
+            bundle.abis.len(),
+            bundle.signatures.len()
+        ),
+    }
+    Ok(())
+}
+
+fn import_abis(
+    path: &Path,
+    overwrite: bool,
+    output: OutputFormat,
+    config: &AppConfig,
+) -> Result<(), AppError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::InvalidRequest(format!("{}: {}", path.display(), e)))?;
+    let bundle: AbiBundle = serde_json::from_str(&content)
+        .map_err(|e| AppError::InvalidRequest(format!("bundle inválido: {}", e)))?;
+    let report = import_bundle(&offline_decoder(config)?, bundle, overwrite)?;
+
+    match output {
+        OutputFormat::Json => print_json(&AbiImportResponse {
+            status: "success".to_string(),
+            imported: report.imported,
+            skipped: report.skipped,
+            signatures: report.signatures,
+        }),
+        OutputFormat::Text => println!(
+            "Importados {} ABIs ({} ya en caché), {} firmas",
+            report.imported, report.skipped, report.signatures
+        ),
+    }
+    Ok(())
+}
+
+// Solo caché y base de firmas: exportar e importar no consultan el explorador
+fn offline_decoder(config: &AppConfig) -> Result<Decoder, AppError> {
+    Ok(Decoder::new(abi_cache_from_config(config)?)
+        .with_signatures(SignatureDb::from_config(config)?))
+}
+
+fn selectors(path: &Path, output: OutputFormat) -> Result<(), AppError> {
+    let (contract, _abi) = load_abi_file(path)?;
+
+    match output {
+        OutputFormat::Json => {
+            let functions: Vec<Value> = contract
+                .functions()
+                .map(|f| {
+                    json!({
+                        "selector": format!("0x{}", hex::encode(f.short_signature())),
+                        "signature": signature_of(&f.name, &f.inputs),
+                    })
+                })
+                .collect();
+            let events: Vec<Value> = contract
+                .events()
+                .map(|e| {
+                    json!({
+                        "topic0": format!("{:?}", e.signature()),
+                        "signature": canonical_signature(
+                            &e.name,
+                            &e.inputs.iter().map(|p| p.kind.clone()).collect::<Vec<_>>(),
+                        ),
+                        "anonymous": e.anonymous,
+                    })
+                })
+                .collect();
+            let errors: Vec<Value> = contract
+                .errors()
+                .map(|e| {
+                    json!({
+                        "selector": format!("0x{}", hex::encode(&e.signature()[..4])),
+                        "signature": signature_of(&e.name, &e.inputs),
+                    })
+                })
+                .collect();
+            print_json(&json!({
+                "functions": functions,
+                "events": events,
+                "errors": errors,
+            }));
+        }
+        OutputFormat::Text => print_selectors(&contract),
+    }
+    Ok(())
+}
+
+fn disasm(bytecode: &str, output: OutputFormat) -> Result<(), AppError> {
+    let bytecode = match bytecode.strip_prefix('@') {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| AppError::InvalidRequest(format!("{}: {}", path, e)))?,
+        None => bytecode.to_string(),
+    };
+    let instructions = disassemble_hex(&bytecode)?;
+
+    match output {
+        OutputFormat::Json => print_json(&instructions),
+        OutputFormat::Text => {
+            for instruction in &instructions {
+                match &instruction.immediate {
+                    Some(data) => println!(
+                        "0x{:04x}  {} {}",
+                        instruction.offset, instruction.mnemonic, data
+                    ),
+                    None => println!("0x{:04x}  {}", instruction.offset, instruction.mnemonic),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Metadatos del token llamado (con --address) para el resumen y los importes;
+// se leen por RPC solo si la firma los usa
+async fn token_metadata(
+    args: &CallArgs,
+    function: &Function,
+    config: &AppConfig,
+) -> Option<TokenMetadata> {
+    let address = args
+        .address
+        .as_ref()
+        .filter(|_| uses_token_metadata(function))?;
+    let target = parse_address(address).and_then(|address| {
+        let chain = config.chain(args.chain_id)?;
+        let upstreams = Upstreams::from_config(config)?;
+        Ok((
+            address,
+            chain.chain_id,
+            TokenResolver::from_config(config, &upstreams)?,
+        ))
+    });
+    let found = match target {
+        Ok((address, chain_id, resolver)) => resolver.resolve(chain_id, &address).await,
+        Err(e) => Err(e),
+    };
+    match found {
+        Ok(found) => found,
+        Err(e) => {
+            warn!(code = e.code(), error = %e, "no se pudieron leer los metadatos del token");
+            None
+        }
+    }
+}
+
+// ABI desde --abi si se indica; si no, caché local o explorador
+async fn load_contract(args: &CallArgs, config: &AppConfig) -> Result<(Contract, Value), AppError> {
+    if let Some(path) = &args.abi {
+        return load_abi_file(path);
+    }
+    let inline = InlineAbi {
+        signature: args.signature.clone(),
+        ..InlineAbi::default()
+    };
+    if let Some(abi) = inline.parse()? {
+        return Ok((decoder::load_contract(&abi)?, abi));
+    }
+    let address = args.address.as_deref().ok_or_else(|| {
+        AppError::InvalidRequest("Indica --address, --abi o --signature".to_string())
+    })?;
+    let contract_address = parse_address(address)?;
+    let chain = config.chain(args.chain_id)?;
+    get_or_fetch_abi(config, chain, &contract_address).await
+}
+
+// Acepta un array ABI, un artefacto de compilación con campo "abi", firmas
+// legibles (una por línea) o una interfaz de Solidity
+fn load_abi_file(path: &Path) -> Result<(Contract, Value), AppError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::InvalidRequest(format!("{}: {}", path.display(), e)))?;
+    let abi = parse_abi_str(&content)?;
+    Ok((decoder::load_contract(&abi)?, abi))
+}
+
+fn parse_address(address: &str) -> Result<Address, AppError> {
+    address
+        .parse::<Address>()
+        .map_err(|e| AppError::InvalidAddress(format!("{}: {}", address, e)))
+}
+
+fn print_call(name: &str, function: Option<&Function>, tokens: &[ethabi::Token]) {
+    match function {
+        Some(function) => {
+            println!(
+                "Función:   {}",
+                signature_of(&function.name, &function.inputs)
+            );
+            println!("Selector:  0x{}", hex::encode(function.short_signature()));
+        }
+        None => println!("Función:   {}", name),
+    }
+    println!("Argumentos:");
+    for (i, token) in tokens.iter().enumerate() {
+        let param = function.and_then(|f| f.inputs.get(i));
+        match param {
+            Some(param) if !param.name.is_empty() => println!(
+                "  [{}] {} ({}): {}",
+                i,
+                param.name,
+                param.kind,
+                format_token(token)
+            ),
+            Some(param) => println!("  [{}] ({}): {}", i, param.kind, format_token(token)),
+            None => println!("  [{}] {}", i, format_token(token)),
+        }
+    }
+}
+
+// Token llamado e importes de los argumentos en sus unidades
+fn print_token(token: Option<&TokenMetadata>, amounts: &[TokenAmount]) {
+    let Some(token) = token else {
+        return;
+    };
+    let label = match (&token.name, &token.symbol) {
+        (Some(name), Some(symbol)) => format!("{} ({})", name, symbol),
+        (Some(text), None) | (None, Some(text)) => text.clone(),
+        (None, None) => "sin nombre".to_string(),
+    };
+    match token.standard {
+        Some(standard) => println!("Token:     {} [{:?}]", label, standard),
+        None => println!("Token:     {}", label),
+    }
+    for amount in amounts {
+        println!(
+            "  [{}] {} = {}",
+            amount.index, amount.argument, amount.formatted
+        );
+    }
+}
+
+fn print_selectors(contract: &Contract) {
+    for function in contract.functions() {
+        println!(
+            "0x{}  {}",
+            hex::encode(function.short_signature()),
+            signature_of(&function.name, &function.inputs)
+        );
+    }
+    for event in contract.events() {
+        let kinds: Vec<_> = event.inputs.iter().map(|p| p.kind.clone()).collect();
+        println!(
+            "{:?}  event {}",
+            event.signature(),
+            canonical_signature(&event.name, &kinds)
+        );
+    }
+    for error in contract.errors() {
+        println!(
+            "0x{}  error {}",
+            hex::encode(&error.signature()[..4]),
+            signature_of(&error.name, &error.inputs)
+        );
+    }
+}
+
+fn signature_of(name: &str, inputs: &[Param]) -> String {
+    let kinds: Vec<_> = inputs.iter().map(|p| p.kind.clone()).collect();
+    canonical_signature(name, &kinds)
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("❌ Error al serializar la salida: {}", e),
+    }
+}
@@ -1,11 +1,403 @@
 use serde_json;
+use std::collections::HashSet;
 use std::fs;
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
 
-use crate::PromptConfig;
+use crate::error::AppError;
+use crate::{
+    AppConfig, AuthConfig, CacheBackend, CacheConfig, ChainConfig, LlmConfig, LogFormat,
+    PromptConfig, RateLimitConfig, RetryConfig, RetryPolicy, ServerConfig, StorageConfig,
+    TimeoutConfig,
+};
 
-pub fn load_prompt_config() -> Result<PromptConfig, Box<dyn std::error::Error>> {
-    let config_path = "src/prompt_config.json";
-    let config_content = fs::read_to_string(config_path)?;
-    let config: PromptConfig = serde_json::from_str(&config_content)?;
+// Configuración de prompt por defecto, embebida en el binario para que un
+// build de release funcione aunque `src/prompt_config.json` no exista.
+const DEFAULT_PROMPT_CONFIG: &str = include_str!("prompt_config.json");
+
+// Archivo de configuración que se usa si existe y no se indica otro
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            server: ServerConfig::default(),
+            storage: StorageConfig::default(),
+            cache: CacheConfig::default(),
+            llm: LlmConfig::default(),
+            timeouts: TimeoutConfig::default(),
+            retry: RetryConfig::default(),
+            auth: AuthConfig::default(),
+            default_chain_id: 421614,
+            chains: vec![ChainConfig {
+                chain_id: 421614,
+                name: "Arbitrum Sepolia".to_string(),
+                explorer_api_url: "https://api-sepolia.arbiscan.io/api".to_string(),
+                api_key_env: Some("ARBISCAN_API_KEY".to_string()),
+                rpc_url: None,
+            }],
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: "127.0.0.1:8080".to_string(),
+            workers: None,
+            cors_origins: vec!["*".to_string()],
+            log_format: LogFormat::default(),
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            abi_dir: PathBuf::from("ABI"),
+            history_db: PathBuf::from("history.db"),
+            prompt_config: None,
+            abi_ttl_secs: None,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            backend: CacheBackend::default(),
+            sqlite_path: PathBuf::from("cache.db"),
+            redis_url: "redis://127.0.0.1:6379".to_string(),
+            redis_prefix: "rdt".to_string(),
+            analysis_ttl_secs: 3600,
+        }
+    }
+}
+
+impl CacheBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheBackend::Fs => "fs",
+            CacheBackend::Sqlite => "sqlite",
+            CacheBackend::Redis => "redis",
+        }
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        LlmConfig {
+            api_url: "https://api.deepseek.com/chat/completions".to_string(),
+            api_key_env: "DEEPSEEK_API_KEY".to_string(),
+            model: None,
+        }
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        TimeoutConfig {
+            explorer_secs: 15,
+            llm_secs: 60,
+            client_request_secs: 10,
+            connect_secs: 5,
+            rpc_secs: 5,
+            token_metadata_secs: 3,
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            explorer: RetryPolicy::default(),
+            llm: RetryPolicy::llm_default(),
+            rpc: RetryPolicy::default(),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8000,
+            breaker_threshold: 5,
+            breaker_cooldown_secs: 30,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Cada intento al LLM puede tardar hasta timeouts.llm_secs: menos reintentos
+    pub fn llm_default() -> Self {
+        RetryPolicy {
+            max_attempts: 2,
+            initial_backoff_ms: 1000,
+            ..RetryPolicy::default()
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            enabled: false,
+            public_paths: ["/healthz", "/readyz", "/openapi.json", "/docs"]
+                .map(String::from)
+                .to_vec(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            per_minute: 60,
+            burst: None,
+            daily_quota: None,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    // Las llamadas al LLM cuestan dinero: presupuesto por defecto más estricto
+    pub fn llm_default() -> Self {
+        RateLimitConfig {
+            per_minute: 5,
+            burst: None,
+            daily_quota: Some(200),
+        }
+    }
+}
+
+impl AppConfig {
+    // Devuelve la cadena solicitada, o la cadena por defecto si no se indica
+    pub fn chain(&self, chain_id: Option<u64>) -> Result<&ChainConfig, AppError> {
+        let chain_id = chain_id.unwrap_or(self.default_chain_id);
+        self.chains
+            .iter()
+            .find(|chain| chain.chain_id == chain_id)
+            .ok_or_else(|| AppError::InvalidRequest(format!("Cadena no soportada: {}", chain_id)))
+    }
+
+    pub fn abi_ttl(&self) -> Option<Duration> {
+        self.storage.abi_ttl_secs.map(Duration::from_secs)
+    }
+
+    // Comprueba toda la configuración y reporta todos los problemas a la vez
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut problems: Vec<String> = Vec::new();
+
+        match self.server.bind.to_socket_addrs() {
+            Ok(addrs) if addrs.len() > 0 => {}
+            Ok(_) => problems.push(format!(
+                "server.bind: '{}' no resuelve a ninguna dirección",
+                self.server.bind
+            )),
+            Err(e) => problems.push(format!(
+                "server.bind: '{}' inválido: {}",
+                self.server.bind, e
+            )),
+        }
+        if self.server.workers == Some(0) {
+            problems.push("server.workers: debe ser al menos 1".to_string());
+        }
+        if self.server.cors_origins.is_empty() {
+            problems.push("server.cors_origins: indica al menos un origen o \"*\"".to_string());
+        }
+        for origin in &self.server.cors_origins {
+            if origin != "*" && !is_http_url(origin) {
+                problems.push(format!(
+                    "server.cors_origins: '{}' no es un origen http(s) válido",
+                    origin
+                ));
+            }
+        }
+
+        if self.storage.abi_dir.exists() && !self.storage.abi_dir.is_dir() {
+            problems.push(format!(
+                "storage.abi_dir: '{}' existe pero no es un directorio",
+                self.storage.abi_dir.display()
+            ));
+        }
+        if self.storage.abi_ttl_secs == Some(0) {
+            problems.push("storage.abi_ttl_secs: debe ser mayor que 0".to_string());
+        }
+        if let Err(e) = load_prompt_config(self.storage.prompt_config.as_deref()) {
+            problems.push(format!("storage.prompt_config: {}", e));
+        }
+
+        let available = match self.cache.backend {
+            CacheBackend::Fs => true,
+            CacheBackend::Sqlite => cfg!(feature = "server"),
+            CacheBackend::Redis => cfg!(feature = "redis"),
+        };
+        if !available {
+            problems.push(format!(
+                "cache.backend: '{}' no está disponible en este build",
+                self.cache.backend.name()
+            ));
+        }
+        if self.cache.backend == CacheBackend::Redis {
+            match Url::parse(&self.cache.redis_url) {
+                Ok(url) if matches!(url.scheme(), "redis" | "rediss") => {}
+                _ => problems.push(format!(
+                    "cache.redis_url: '{}' no es una URL redis:// o rediss://",
+                    self.cache.redis_url
+                )),
+            }
+        }
+
+        if !is_http_url(&self.llm.api_url) {
+            problems.push(format!(
+                "llm.api_url: '{}' no es una URL http(s)",
+                self.llm.api_url
+            ));
+        }
+        if self.llm.api_key_env.is_empty() {
+            problems.push("llm.api_key_env: no puede estar vacío".to_string());
+        }
+
+        for (name, secs) in [
+            ("timeouts.explorer_secs", self.timeouts.explorer_secs),
+            ("timeouts.llm_secs", self.timeouts.llm_secs),
+            (
+                "timeouts.client_request_secs",
+                self.timeouts.client_request_secs,
+            ),
+            ("timeouts.connect_secs", self.timeouts.connect_secs),
+            ("timeouts.rpc_secs", self.timeouts.rpc_secs),
+            (
+                "timeouts.token_metadata_secs",
+                self.timeouts.token_metadata_secs,
+            ),
+        ] {
+            if secs == 0 {
+                problems.push(format!("{}: debe ser mayor que 0", name));
+            }
+        }
+
+        for (upstream, policy) in [
+            ("explorer", &self.retry.explorer),
+            ("llm", &self.retry.llm),
+            ("rpc", &self.retry.rpc),
+        ] {
+            if policy.max_attempts == 0 || policy.breaker_threshold == 0 {
+                problems.push(format!(
+                    "retry.{}: max_attempts y breaker_threshold deben ser al menos 1",
+                    upstream
+                ));
+            }
+            if policy.initial_backoff_ms > policy.max_backoff_ms {
+                problems.push(format!(
+                    "retry.{}: initial_backoff_ms no puede superar max_backoff_ms",
+                    upstream
+                ));
+            }
+        }
+
+        if self.auth.enabled && self.auth.keys.is_empty() {
+            problems.push("auth.keys: auth.enabled requiere al menos una clave".to_string());
+        }
+        let mut names = HashSet::new();
+        for key in &self.auth.keys {
+            if key.name.is_empty() || !names.insert(key.name.as_str()) {
+                problems.push(format!(
+                    "auth.keys: nombre '{}' vacío o duplicado",
+                    key.name
+                ));
+            }
+            let hash = key.key_hash.strip_prefix("0x").unwrap_or("");
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                problems.push(format!(
+                    "auth.keys[{}].key_hash: se espera 0x seguido de 64 dígitos hex (usa `hash-key`)",
+                    key.name
+                ));
+            }
+            for (budget, limit) in [("decode", &key.decode), ("llm", &key.llm)] {
+                if limit.per_minute == 0 || limit.burst == Some(0) || limit.daily_quota == Some(0) {
+                    problems.push(format!(
+                        "auth.keys[{}].{}: per_minute, burst y daily_quota deben ser mayores que 0",
+                        key.name, budget
+                    ));
+                }
+            }
+        }
+
+        if self.chains.is_empty() {
+            problems.push("chains: el registro de cadenas está vacío".to_string());
+        }
+        let mut seen = HashSet::new();
+        for chain in &self.chains {
+            if !seen.insert(chain.chain_id) {
+                problems.push(format!("chains: chain_id {} duplicado", chain.chain_id));
+            }
+            if !is_http_url(&chain.explorer_api_url) {
+                problems.push(format!(
+                    "chains[{}].explorer_api_url: '{}' no es una URL http(s)",
+                    chain.chain_id, chain.explorer_api_url
+                ));
+            }
+            if let Some(rpc_url) = chain.rpc_url.as_deref().filter(|url| !is_http_url(url)) {
+                problems.push(format!(
+                    "chains[{}].rpc_url: '{}' no es una URL http(s)",
+                    chain.chain_id, rpc_url
+                ));
+            }
+        }
+        if !seen.contains(&self.default_chain_id) {
+            problems.push(format!(
+                "default_chain_id: {} no está en el registro de cadenas",
+                self.default_chain_id
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::ConfigInvalid(format!(
+                "  - {}",
+                problems.join("\n  - ")
+            )))
+        }
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    Url::parse(value)
+        .map(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+        .unwrap_or(false)
+}
+
+// Carga la configuración del servidor desde un archivo TOML. Sin ruta
+// explícita se usa `config.toml` si existe y, si no, los valores por defecto.
+pub fn load_app_config(path: Option<&Path>) -> Result<AppConfig, AppError> {
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => Path::new(DEFAULT_CONFIG_FILE),
+        None => return Ok(AppConfig::default()),
+    };
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::ConfigInvalid(format!("  - {}: {}", path.display(), e)))?;
+    toml::from_str(&content)
+        .map_err(|e| AppError::ConfigInvalid(format!("  - {}: {}", path.display(), e)))
+}
+
+pub fn load_prompt_config(path: Option<&Path>) -> Result<PromptConfig, AppError> {
+    let (config_path, config_content) = match path {
+        Some(path) => (
+            path.display().to_string(),
+            fs::read_to_string(path)
+                .map_err(|e| AppError::PromptConfigInvalid(format!("{}: {}", path.display(), e)))?,
+        ),
+        None => ("<embebida>".to_string(), DEFAULT_PROMPT_CONFIG.to_string()),
+    };
+    let config: PromptConfig = serde_json::from_str(&config_content)
+        .map_err(|e| AppError::PromptConfigInvalid(format!("{}: {}", config_path, e)))?;
     Ok(config)
 }
@@ -1,37 +1,347 @@
-use ethabi::{Contract, Token};
+use ethabi::ethereum_types::{H256, U256};
+use ethabi::{Contract, Function, Log, ParamType, RawLog, Token};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::error::AppError;
+use crate::CallCandidate;
+
+// Selectores de los errores estándar de Solidity
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0]; // Error(string)
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71]; // Panic(uint256)
+
+// Error de revert decodificado (estándar o definido en el ABI)
+#[derive(Serialize)]
+pub struct DecodedError {
+    pub name: String,
+    pub signature: String,
+    pub arguments: Vec<String>,
+}
+
+// Llamada decodificada con argumentos tipados (nombre, tipo y valor JSON)
+#[derive(Serialize)]
+pub struct DecodedCall {
+    pub function_name: String,
+    pub signature: String,
+    pub selector: String,
+    pub arguments: Vec<DecodedArgument>,
+}
+
+#[derive(Serialize)]
+pub struct DecodedArgument {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Value,
+}
+
+pub fn parse_hex(data: &str) -> Result<Vec<u8>, AppError> {
+    let data = data.trim();
+    hex::decode(data.strip_prefix("0x").unwrap_or(data))
+        .map_err(|e| AppError::InvalidHex(e.to_string()))
+}
 
 pub fn decode_function_call(
     contract: &Contract,
     call_data: &str,
-) -> Result<(String, Vec<Token>), Box<dyn std::error::Error>> {
-    let call_data_bytes = hex::decode(call_data.strip_prefix("0x").unwrap_or(call_data))?;
+) -> Result<(String, Vec<Token>), AppError> {
+    decode_function(contract, call_data).map(|(function, tokens)| (function.name.clone(), tokens))
+}
+
+// Función del ABI que coincidió con la llamada y sus argumentos
+pub type DecodedFunction<'a> = (&'a Function, Vec<Token>);
+
+// Como `decode_function_call`, pero devuelve la función del ABI que coincidió.
+// Con varios candidatos gana el primero (por nombre) que decodifica; si
+// ninguno lo hace, el error lleva el motivo de cada uno.
+pub fn decode_function<'a>(
+    contract: &'a Contract,
+    call_data: &str,
+) -> Result<DecodedFunction<'a>, AppError> {
+    let call_data_bytes = parse_hex(call_data)?;
+    let candidates = candidates_for(contract, &call_data_bytes)?;
+    choose_candidate(candidates, &call_data_bytes)
+}
+
+// `decode_function` junto con `reported_candidates`, decodificando cada
+// candidato una sola vez
+pub fn decode_with_candidates<'a>(
+    contract: &'a Contract,
+    call_data: &str,
+) -> (
+    Result<DecodedFunction<'a>, AppError>,
+    Option<Vec<CallCandidate>>,
+) {
+    let candidates = parse_hex(call_data).and_then(|bytes| {
+        let candidates = candidates_for(contract, &bytes)?;
+        Ok((candidates, bytes))
+    });
+    match candidates {
+        Ok((candidates, bytes)) => {
+            let reported = report_candidates(&candidates);
+            (choose_candidate(candidates, &bytes), reported)
+        }
+        Err(e) => (Err(e), None),
+    }
+}
+
+fn choose_candidate<'a>(
+    candidates: Vec<Candidate<'a>>,
+    call_data_bytes: &[u8],
+) -> Result<DecodedFunction<'a>, AppError> {
+    let mut errors = Vec::new();
+    for candidate in candidates {
+        match candidate.result {
+            Ok(tokens) => return Ok((candidate.function, tokens)),
+            Err(e) => errors.push(format!("{}: {}", candidate.signature, e)),
+        }
+    }
+    if errors.is_empty() {
+        return Err(AppError::SelectorNotFound(format!(
+            "0x{}",
+            hex::encode(&call_data_bytes[..4])
+        )));
+    }
+    Err(AppError::ArgumentsMismatch(errors.join("; ")))
+}
+
+// Función del ABI cuyo selector coincide con la llamada, con el resultado de
+// decodificar los argumentos con ella
+pub struct Candidate<'a> {
+    pub function: &'a Function,
+    pub signature: String,
+    pub result: Result<Vec<Token>, String>,
+}
+
+// Todas las funciones con el selector de la llamada. Varias firmas distintas
+// son una colisión (o sobrecargas mal resueltas en ABIs combinados de proxy e
+// implementación); la misma firma repetida cuenta una sola vez.
+pub fn function_candidates<'a>(
+    contract: &'a Contract,
+    call_data: &str,
+) -> Result<Vec<Candidate<'a>>, AppError> {
+    candidates_for(contract, &parse_hex(call_data)?)
+}
+
+// Candidatos que acompañan al resultado: todos si la llamada es ambigua o si
+// alguno no decodifica; None en el caso normal de una única función que encaja
+pub fn reported_candidates(contract: &Contract, call_data: &str) -> Option<Vec<CallCandidate>> {
+    report_candidates(&function_candidates(contract, call_data).ok()?)
+}
+
+fn report_candidates(candidates: &[Candidate<'_>]) -> Option<Vec<CallCandidate>> {
+    let ambiguous = candidates.len() > 1 || candidates.iter().any(|c| c.result.is_err());
+    ambiguous.then(|| candidates.iter().map(CallCandidate::from).collect())
+}
+
+impl From<&Candidate<'_>> for CallCandidate {
+    fn from(candidate: &Candidate<'_>) -> Self {
+        CallCandidate {
+            function_name: candidate.function.name.clone(),
+            signature: candidate.signature.clone(),
+            decoded: candidate.result.is_ok(),
+            arguments: candidate
+                .result
+                .as_ref()
+                .ok()
+                .map(|tokens| tokens.iter().map(|token| format!("{:?}", token)).collect()),
+            error: candidate.result.as_ref().err().cloned(),
+        }
+    }
+}
+
+fn candidates_for<'a>(
+    contract: &'a Contract,
+    call_data: &[u8],
+) -> Result<Vec<Candidate<'a>>, AppError> {
+    if call_data.len() < 4 {
+        return Err(AppError::CalldataTooShort(call_data.len()));
+    }
+    let (selector, input) = call_data.split_at(4);
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for function in contract.functions() {
+        if function.short_signature() != selector {
+            continue;
+        }
+        let signature = function_signature(function);
+        if candidates.iter().any(|c| c.signature == signature) {
+            continue;
+        }
+        candidates.push(Candidate {
+            function,
+            signature,
+            result: function.decode_input(input).map_err(|e| e.to_string()),
+        });
+    }
+    Ok(candidates)
+}
+
+pub fn describe_call(function: &Function, tokens: &[Token]) -> DecodedCall {
+    DecodedCall {
+        function_name: function.name.clone(),
+        signature: function_signature(function),
+        selector: format!("0x{}", hex::encode(function.short_signature())),
+        arguments: function
+            .inputs
+            .iter()
+            .zip(tokens)
+            .map(|(param, token)| DecodedArgument {
+                name: param.name.clone(),
+                kind: param.kind.to_string(),
+                value: token_to_json(token),
+            })
+            .collect(),
+    }
+}
+
+// Selector de 4 bytes (0x...) de una firma como `transfer(address,uint256)`
+pub fn selector(signature: &str) -> Result<String, AppError> {
+    let normalized: String = signature.chars().filter(|c| !c.is_whitespace()).collect();
+    if !normalized.contains('(') || !normalized.ends_with(')') {
+        return Err(AppError::InvalidRequest(format!(
+            "Firma inválida '{}': se espera nombre(tipo1,tipo2)",
+            signature
+        )));
+    }
+    let hash = Keccak256::digest(normalized.as_bytes());
+    Ok(format!("0x{}", hex::encode(&hash[..4])))
+}
+
+// Decodifica un log con el evento del ABI cuyo topic0 coincide; los eventos
+// anónimos (sin topic0) se prueban uno a uno.
+pub fn decode_log(contract: &Contract, topics: &[H256], data: &str) -> Result<Log, AppError> {
+    let data = parse_hex(data)?;
+    let raw = RawLog {
+        topics: topics.to_vec(),
+        data,
+    };
+
+    let topic0 = topics.first();
+    for event in contract.events() {
+        if !event.anonymous && Some(&event.signature()) != topic0 {
+            continue;
+        }
+        if let Ok(log) = event.parse_log(raw.clone()) {
+            return Ok(log);
+        }
+    }
 
-    if call_data_bytes.len() < 4 {
-        return Err("Datos de llamada muy cortos".into());
+    Err(AppError::EventNotFound(
+        topic0
+            .map(|topic| format!("{:?}", topic))
+            .unwrap_or_else(|| "(sin topics)".to_string()),
+    ))
+}
+
+// Decodifica los datos de un revert: Error(string), Panic(uint256) o un
+// error personalizado declarado en el ABI.
+pub fn decode_error(contract: &Contract, revert_data: &str) -> Result<DecodedError, AppError> {
+    let bytes = parse_hex(revert_data)?;
+    if bytes.len() < 4 {
+        return Err(AppError::CalldataTooShort(bytes.len()));
+    }
+    let (selector, payload) = bytes.split_at(4);
+
+    let standard = if selector == ERROR_STRING_SELECTOR {
+        Some(("Error", vec![ParamType::String]))
+    } else if selector == PANIC_SELECTOR {
+        Some(("Panic", vec![ParamType::Uint(256)]))
+    } else {
+        None
+    };
+    if let Some((name, kinds)) = standard {
+        let tokens = ethabi::decode(&kinds, payload).map_err(|e| {
+            AppError::ArgumentsMismatch(format!("{}: {}", canonical_signature(name, &kinds), e))
+        })?;
+        return Ok(DecodedError {
+            name: name.to_string(),
+            signature: canonical_signature(name, &kinds),
+            arguments: tokens.iter().map(format_token).collect(),
+        });
+    }
+
+    for error in contract.errors() {
+        if error.signature()[..4] != *selector {
+            continue;
+        }
+        if let Ok(tokens) = error.decode(payload) {
+            let kinds: Vec<ParamType> = error.inputs.iter().map(|p| p.kind.clone()).collect();
+            return Ok(DecodedError {
+                name: error.name.clone(),
+                signature: canonical_signature(&error.name, &kinds),
+                arguments: tokens.iter().map(format_token).collect(),
+            });
+        }
     }
 
-    let function_selector = &call_data_bytes[0..4];
-    let input_data = &call_data_bytes[4..];
+    Err(AppError::SelectorNotFound(format!(
+        "0x{}",
+        hex::encode(selector)
+    )))
+}
 
-    for (name, functions) in &contract.functions {
-        for function in functions {
-            let computed_selector = function.short_signature();
-            if computed_selector == function_selector {
-                match function.decode_input(input_data) {
-                    Ok(result) => {
-                        return Ok((name.clone(), result));
-                    }
-                    Err(_) => {
-                        // Log or handle decoding errors specifically if needed
-                    }
-                }
-            }
+// Valor JSON tipado de un token: enteros y direcciones como strings (para no
+// perder precisión en JavaScript), bytes en hex y arrays/tuplas como arrays.
+pub fn token_to_json(token: &Token) -> Value {
+    match token {
+        Token::Bool(value) => json!(value),
+        Token::String(value) => json!(value),
+        Token::FixedArray(tokens) | Token::Array(tokens) | Token::Tuple(tokens) => {
+            Value::Array(tokens.iter().map(token_to_json).collect())
         }
+        other => json!(format_token(other)),
     }
+}
+
+// Representación legible de un token: direcciones y bytes en hex con 0x,
+// enteros en decimal (con signo para intN) y arrays/tuplas anidados.
+pub fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{:?}", address),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        Token::Int(value) if value.bit(255) => {
+            // Complemento a dos: el valor absoluto es !value + 1
+            format!("-{}", (!*value).overflowing_add(U256::one()).0)
+        }
+        Token::Int(value) => value.to_string(),
+        Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{:?}", value),
+        Token::FixedArray(tokens) | Token::Array(tokens) => format!(
+            "[{}]",
+            tokens
+                .iter()
+                .map(format_token)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Token::Tuple(tokens) => format!(
+            "({})",
+            tokens
+                .iter()
+                .map(format_token)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+pub fn function_signature(function: &Function) -> String {
+    let kinds: Vec<ParamType> = function.inputs.iter().map(|p| p.kind.clone()).collect();
+    canonical_signature(&function.name, &kinds)
+}
 
-    Err(format!(
-        "No se encontró función coincidente para el selector: 0x{}",
-        hex::encode(function_selector)
+// Firma canónica `nombre(tipo1,tipo2)` de la que se derivan selectores y topics
+pub fn canonical_signature(name: &str, kinds: &[ParamType]) -> String {
+    format!(
+        "{}({})",
+        name,
+        kinds
+            .iter()
+            .map(|kind| kind.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
     )
-    .into())
 }
@@ -0,0 +1,556 @@
+use async_trait::async_trait;
+use ethabi::ethereum_types::H256;
+use ethabi::{Address, Contract, Log};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
+
+use crate::decode::{self, DecodedError};
+use crate::error::AppError;
+use crate::human_abi::check_param_types;
+use crate::store::{blocking, SignatureDb};
+
+// Procedencia de un ABI guardado por el propio servicio
+pub const SOURCE_UPLOAD: &str = "upload";
+// Archivos antiguos de `ABI/` con el array ABI sin metadatos
+pub const SOURCE_LEGACY: &str = "legacy";
+
+// Lo que devuelve un proveedor: el ABI y los metadatos que conozca
+#[derive(Clone, Default)]
+pub struct FetchedAbi {
+    pub abi: Value,
+    pub contract_name: Option<String>,
+    pub compiler_version: Option<String>,
+    pub implementation: Option<Address>, // Si el contrato es un proxy
+}
+
+// Entrada de la caché: el ABI (JSON sin procesar) y su procedencia. Los
+// campos opcionales faltan en entradas escritas por versiones anteriores.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CachedAbi {
+    pub chain_id: Option<u64>, // None en entradas antiguas sin metadatos
+    pub address: Address,
+    pub source: String,          // Nombre del proveedor, "upload" o "legacy"
+    pub fetched_at: Option<u64>, // Segundos unix
+    #[serde(default)]
+    pub content_hash: Option<String>, // hash() al guardar; distinto si se editó a mano
+    #[serde(default)]
+    pub contract_name: Option<String>,
+    #[serde(default)]
+    pub compiler_version: Option<String>,
+    #[serde(default)]
+    pub implementation: Option<Address>,
+    pub abi: Value,
+}
+
+impl CachedAbi {
+    pub fn new(chain_id: u64, address: Address, source: &str, fetched: FetchedAbi) -> Self {
+        let mut cached = CachedAbi {
+            chain_id: Some(chain_id),
+            address,
+            source: source.to_string(),
+            fetched_at: now_secs(),
+            content_hash: None,
+            contract_name: fetched.contract_name,
+            compiler_version: fetched.compiler_version,
+            implementation: fetched.implementation,
+            abi: fetched.abi,
+        };
+        cached.content_hash = Some(cached.hash());
+        cached
+    }
+
+    // keccak256 del ABI serializado; identifica el contenido entre réplicas
+    pub fn hash(&self) -> String {
+        format!(
+            "0x{}",
+            hex::encode(Keccak256::digest(self.abi.to_string().as_bytes()))
+        )
+    }
+
+    // La entrada es del contrato pedido (las entradas antiguas sin cadena valen para cualquiera)
+    pub fn matches(&self, chain_id: u64, address: &Address) -> bool {
+        self.address == *address && self.chain_id.is_none_or(|chain| chain == chain_id)
+    }
+
+    // El ABI ya no coincide con el hash registrado al guardarlo
+    pub fn is_modified(&self) -> bool {
+        self.content_hash
+            .as_ref()
+            .is_some_and(|hash| *hash != self.hash())
+    }
+}
+
+// Caché de ABIs indexada por cadena y dirección.
+// Implementaciones: `abi::FsAbiCache` (carpeta `ABI/`) y `abi::MemoryAbiCache`.
+pub trait AbiCache: Send + Sync {
+    fn get(&self, chain_id: u64, address: &Address) -> Result<Option<CachedAbi>, AppError>;
+    fn put(&self, entry: &CachedAbi) -> Result<(), AppError>;
+    // Devuelve false si la entrada no existía
+    fn remove(&self, chain_id: u64, address: &Address) -> Result<bool, AppError>;
+    fn list(&self) -> Result<Vec<CachedAbi>, AppError>;
+}
+
+impl<C: AbiCache + ?Sized> AbiCache for Box<C> {
+    fn get(&self, chain_id: u64, address: &Address) -> Result<Option<CachedAbi>, AppError> {
+        (**self).get(chain_id, address)
+    }
+
+    fn put(&self, entry: &CachedAbi) -> Result<(), AppError> {
+        (**self).put(entry)
+    }
+
+    fn remove(&self, chain_id: u64, address: &Address) -> Result<bool, AppError> {
+        (**self).remove(chain_id, address)
+    }
+
+    fn list(&self) -> Result<Vec<CachedAbi>, AppError> {
+        (**self).list()
+    }
+}
+
+// Origen de ABIs que no están en caché, p. ej. un explorador de bloques
+// (`abi::ExplorerAbiProvider`, con la feature `explorer`).
+#[async_trait]
+pub trait AbiProvider: Send + Sync {
+    async fn fetch_abi(&self, chain_id: u64, address: &Address) -> Result<FetchedAbi, AppError>;
+
+    // Se guarda como `source` de las entradas que obtiene
+    fn name(&self) -> &str {
+        "provider"
+    }
+}
+
+// Contratos ya parseados que se mantienen en memoria
+const CONTRACT_CACHE_CAPACITY: usize = 1024;
+
+// Espera máxima tras una revalidación fallida antes de volver a intentarla
+// (el TTL si es menor); mientras, se sirve la copia caducada
+const REVALIDATION_RETRY: Duration = Duration::from_secs(300);
+
+type AbiKey = (u64, Address);
+type AbiResult = Result<(Contract, Value), AppError>;
+type Resolved = (Contract, CachedAbi);
+type InFlight = HashMap<AbiKey, Arc<OnceCell<Result<Resolved, AppError>>>>;
+
+// Punto de entrada de la librería: resuelve ABIs (caché y, si falta, el
+// proveedor) y decodifica llamadas, logs y errores de revert.
+// Las cachés persistentes se comparten con el pool de hilos bloqueantes
+// (`store::blocking`) para no leer ni escribir desde el hilo del runtime.
+pub struct Decoder {
+    cache: Arc<dyn AbiCache>,
+    provider: Option<Box<dyn AbiProvider>>,
+    ttl: Option<Duration>, // Antigüedad a partir de la cual se revalida con el proveedor
+    signatures: Option<Arc<SignatureDb>>,
+    contracts: RwLock<HashMap<AbiKey, Resolved>>,
+    // Resoluciones en curso: las peticiones concurrentes del mismo contrato
+    // esperan a la primera en vez de repetir la lectura o la llamada al proveedor
+    in_flight: Mutex<InFlight>,
+    // Revalidaciones fallidas: hasta cuándo no se vuelve a preguntar al proveedor
+    retry_after: Mutex<HashMap<AbiKey, Instant>>,
+}
+
+impl Decoder {
+    pub fn new(cache: impl AbiCache + 'static) -> Self {
+        Decoder {
+            cache: Arc::new(cache),
+            provider: None,
+            ttl: None,
+            signatures: None,
+            contracts: RwLock::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            retry_after: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_provider(mut self, provider: impl AbiProvider + 'static) -> Self {
+        self.provider = Some(Box::new(provider));
+        self
+    }
+
+    // Sin TTL las entradas no caducan. Los ABIs subidos por el usuario nunca
+    // se revalidan.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    // Registra las firmas de cada contrato resuelto para `lookup_signatures`
+    pub fn with_signatures(mut self, signatures: SignatureDb) -> Self {
+        self.signatures = Some(Arc::new(signatures));
+        self
+    }
+
+    // Firmas vistas en cualquier ABI para un selector o topic; vacío sin base de firmas
+    pub fn lookup_signatures(&self, selector: &str) -> Result<Vec<String>, AppError> {
+        match &self.signatures {
+            Some(signatures) => signatures.lookup(selector),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn get_abi(&self, chain_id: u64, address: &Address) -> AbiResult {
+        let key = (chain_id, *address);
+        if let Some((contract, cached)) = self.memory_get(&key)? {
+            if !self.needs_revalidation(&key, &cached)? {
+                return Ok((contract, cached.abi));
+            }
+        }
+
+        let cell = self
+            .lock_in_flight()?
+            .entry(key)
+            .or_insert_with(|| Arc::new(OnceCell::new()))
+            .clone();
+        // Si la petición que resuelve se cancela, otra de las que esperan toma el relevo
+        let result = cell
+            .get_or_init(|| self.resolve(chain_id, address))
+            .await
+            .clone();
+
+        let mut in_flight = self.lock_in_flight()?;
+        if in_flight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            in_flight.remove(&key);
+        }
+        result.map(|(contract, cached)| (contract, cached.abi))
+    }
+
+    // Caché persistente y, si falta o caducó, el proveedor. El contrato
+    // resuelto queda en memoria para las siguientes peticiones.
+    async fn resolve(&self, chain_id: u64, address: &Address) -> Result<Resolved, AppError> {
+        let (cache, key) = (self.cache.clone(), *address);
+        let cached = match blocking(move || cache.get(chain_id, &key)).await? {
+            // Nunca se decodifica con el ABI de otro contrato; se vuelve a obtener
+            Some(cached) if !cached.matches(chain_id, address) => {
+                warn!(
+                    chain_id,
+                    contract = ?address,
+                    recorded_chain_id = cached.chain_id,
+                    recorded_contract = ?cached.address,
+                    "la entrada de la caché pertenece a otro contrato; se descarta"
+                );
+                None
+            }
+            // Sin fecha de descarga (escrita antes de guardar la procedencia): se
+            // toma la de esta lectura en vez de darla por caducada siempre
+            Some(mut cached) if cached.fetched_at.is_none() => {
+                cached.fetched_at = now_secs();
+                let (cache, stamped) = (self.cache.clone(), cached.clone());
+                if let Err(e) = blocking(move || cache.put(&stamped)).await {
+                    warn!(
+                        chain_id,
+                        contract = ?address,
+                        code = e.code(),
+                        error = %e,
+                        "no se pudo fechar la entrada de la caché"
+                    );
+                }
+                Some(cached)
+            }
+            cached => cached,
+        };
+        let key = (chain_id, *address);
+        let resolved = match cached {
+            Some(cached)
+                if self.provider.is_some() && self.needs_revalidation(&key, &cached)? =>
+            {
+                self.revalidate(chain_id, address, cached).await?
+            }
+            Some(cached) => (load_contract(&cached.abi)?, cached),
+            None => self.fetch(chain_id, address).await?,
+        };
+        if resolved.1.is_modified() {
+            warn!(chain_id, contract = ?address, "el ABI en caché no coincide con su hash: se editó a mano");
+        }
+        self.remember_async(key, &resolved).await?;
+        Ok(resolved)
+    }
+
+    // Entrada caducada: se vuelve a pedir al proveedor; si falla se sigue
+    // sirviendo la copia guardada y no se reintenta hasta pasado
+    // `REVALIDATION_RETRY` (o el TTL)
+    async fn revalidate(
+        &self,
+        chain_id: u64,
+        address: &Address,
+        stale: CachedAbi,
+    ) -> Result<Resolved, AppError> {
+        let key = (chain_id, *address);
+        match self.fetch(chain_id, address).await {
+            Ok(fresh) => {
+                self.lock_retry_after()?.remove(&key);
+                if fresh.1.content_hash.as_deref() != Some(stale.hash().as_str()) {
+                    info!(chain_id, contract = ?address, "el ABI cambió al revalidarlo");
+                }
+                Ok(fresh)
+            }
+            Err(e) => {
+                let wait = self
+                    .ttl
+                    .map_or(REVALIDATION_RETRY, |ttl| ttl.min(REVALIDATION_RETRY));
+                warn!(
+                    chain_id,
+                    contract = ?address,
+                    code = e.code(),
+                    error = %e,
+                    retry_in_secs = wait.as_secs(),
+                    "no se pudo revalidar el ABI caducado; se usa la copia en caché"
+                );
+                let now = Instant::now();
+                let mut retry_after = self.lock_retry_after()?;
+                retry_after.retain(|_, until| *until > now);
+                retry_after.insert(key, now + wait);
+                Ok((load_contract(&stale.abi)?, stale))
+            }
+        }
+    }
+
+    // Caducada y sin una revalidación fallida reciente
+    fn needs_revalidation(&self, key: &AbiKey, cached: &CachedAbi) -> Result<bool, AppError> {
+        if !self.is_stale(cached) {
+            return Ok(false);
+        }
+        let retry_after = self.lock_retry_after()?;
+        Ok(retry_after
+            .get(key)
+            .is_none_or(|until| Instant::now() >= *until))
+    }
+
+    fn is_stale(&self, cached: &CachedAbi) -> bool {
+        let Some(ttl) = self.ttl else {
+            return false;
+        };
+        if cached.source == SOURCE_UPLOAD {
+            return false;
+        }
+        // `resolve` fecha las entradas que no la tienen; solo quedan sin fecha
+        // con el reloj anterior a 1970, y no se revalidan en cada petición
+        match (cached.fetched_at, now_secs()) {
+            (Some(fetched_at), Some(now)) => now.saturating_sub(fetched_at) >= ttl.as_secs(),
+            _ => false,
+        }
+    }
+
+    // Entradas de la caché persistente, para administración
+    pub fn cached_abis(&self) -> Result<Vec<CachedAbi>, AppError> {
+        self.cache.list()
+    }
+
+    pub fn cached_abi(
+        &self,
+        chain_id: u64,
+        address: &Address,
+    ) -> Result<Option<CachedAbi>, AppError> {
+        self.cache.get(chain_id, address)
+    }
+
+    // Vuelve a pedir el ABI al proveedor aunque esté en caché y lo reemplaza
+    pub async fn refresh_abi(
+        &self,
+        chain_id: u64,
+        address: &Address,
+    ) -> Result<CachedAbi, AppError> {
+        let resolved = self.fetch(chain_id, address).await?;
+        self.remember_async((chain_id, *address), &resolved).await?;
+        Ok(resolved.1)
+    }
+
+    // Guarda un ABI aportado por el usuario (p. ej. contrato no verificado);
+    // solo se acepta si ethabi lo puede cargar y sus tipos son válidos
+    pub fn store_abi(
+        &self,
+        chain_id: u64,
+        address: &Address,
+        abi: Value,
+        source: &str,
+    ) -> Result<CachedAbi, AppError> {
+        let contract = load_contract(&abi)?;
+        let fetched = FetchedAbi {
+            abi,
+            ..FetchedAbi::default()
+        };
+        let cached = CachedAbi::new(chain_id, *address, source, fetched);
+        self.cache.put(&cached)?;
+        self.remember((chain_id, *address), &(contract, cached.clone()))?;
+        info!(chain_id, contract = ?address, source, "ABI guardado en caché");
+        Ok(cached)
+    }
+
+    // Guarda una entrada tal cual (con su procedencia), p. ej. desde un bundle
+    // exportado por otra instancia
+    pub fn import_abi(&self, cached: CachedAbi) -> Result<(), AppError> {
+        let chain_id = cached.chain_id.ok_or_else(|| {
+            AppError::InvalidRequest(format!("ABI de {:?} sin cadena", cached.address))
+        })?;
+        let contract = load_contract(&cached.abi)?;
+        self.cache.put(&cached)?;
+        self.remember((chain_id, cached.address), &(contract, cached))
+    }
+
+    // Base de firmas completa; vacía sin base de firmas
+    pub fn signature_entries(&self) -> Result<BTreeMap<String, Vec<String>>, AppError> {
+        match &self.signatures {
+            Some(signatures) => signatures.entries(),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    pub fn import_signatures(
+        &self,
+        selector: &str,
+        signatures: Vec<String>,
+    ) -> Result<(), AppError> {
+        match &self.signatures {
+            Some(db) => db.merge(selector, signatures),
+            None => Ok(()),
+        }
+    }
+
+    // Borra la entrada de la caché persistente y de memoria
+    pub fn evict_abi(&self, chain_id: u64, address: &Address) -> Result<bool, AppError> {
+        self.contracts
+            .write()
+            .map_err(|_| {
+                AppError::Internal("Lock de la caché de contratos envenenado".to_string())
+            })?
+            .remove(&(chain_id, *address));
+        let removed = self.cache.remove(chain_id, address)?;
+        if removed {
+            info!(chain_id, contract = ?address, "ABI eliminado de la caché");
+        }
+        Ok(removed)
+    }
+
+    async fn fetch(&self, chain_id: u64, address: &Address) -> Result<Resolved, AppError> {
+        let provider = self.provider.as_ref().ok_or_else(|| {
+            AppError::NotFound(format!(
+                "ABI de {:?} (cadena {}) no está en caché y no hay proveedor configurado",
+                address, chain_id
+            ))
+        })?;
+
+        info!(chain_id, contract = ?address, "consultando ABI al proveedor");
+        let fetched = provider.fetch_abi(chain_id, address).await?;
+        // Se valida antes de guardar para no cachear ABIs inservibles
+        let contract = load_contract(&fetched.abi)?;
+        let cached = CachedAbi::new(chain_id, *address, provider.name(), fetched);
+        let cache = self.cache.clone();
+        let cached = blocking(move || cache.put(&cached).map(|()| cached)).await?;
+        Ok((contract, cached))
+    }
+
+    // Contrato recién resuelto: a memoria y a la base de firmas. Un fallo de
+    // la base de firmas no impide decodificar.
+    fn remember(&self, key: AbiKey, resolved: &Resolved) -> Result<(), AppError> {
+        if let Some(signatures) = &self.signatures {
+            if let Err(e) = signatures.record(&resolved.0) {
+                signatures_failed(key, &e);
+            }
+        }
+        self.memory_put(key, resolved)
+    }
+
+    // `remember` desde código async: la base de firmas se escribe en el pool
+    // de hilos bloqueantes
+    async fn remember_async(&self, key: AbiKey, resolved: &Resolved) -> Result<(), AppError> {
+        if let Some(signatures) = self.signatures.clone() {
+            let contract = resolved.0.clone();
+            if let Err(e) = blocking(move || signatures.record(&contract)).await {
+                signatures_failed(key, &e);
+            }
+        }
+        self.memory_put(key, resolved)
+    }
+
+    fn memory_get(&self, key: &AbiKey) -> Result<Option<Resolved>, AppError> {
+        let contracts = self.contracts.read().map_err(|_| {
+            AppError::Internal("Lock de la caché de contratos envenenado".to_string())
+        })?;
+        Ok(contracts.get(key).cloned())
+    }
+
+    fn memory_put(&self, key: AbiKey, resolved: &Resolved) -> Result<(), AppError> {
+        let mut contracts = self.contracts.write().map_err(|_| {
+            AppError::Internal("Lock de la caché de contratos envenenado".to_string())
+        })?;
+        // Al llenarse se descarta una entrada cualquiera: sigue en la caché persistente
+        if contracts.len() >= CONTRACT_CACHE_CAPACITY && !contracts.contains_key(&key) {
+            if let Some(evicted) = contracts.keys().next().copied() {
+                contracts.remove(&evicted);
+                debug!(chain_id = evicted.0, contract = ?evicted.1, "contrato descartado de la caché en memoria");
+            }
+        }
+        contracts.insert(key, resolved.clone());
+        Ok(())
+    }
+
+    fn lock_retry_after(&self) -> Result<MutexGuard<'_, HashMap<AbiKey, Instant>>, AppError> {
+        self.retry_after.lock().map_err(|_| {
+            AppError::Internal("Lock de revalidaciones fallidas envenenado".to_string())
+        })
+    }
+
+    fn lock_in_flight(&self) -> Result<MutexGuard<'_, InFlight>, AppError> {
+        self.in_flight
+            .lock()
+            .map_err(|_| AppError::Internal("Lock de resoluciones en curso envenenado".to_string()))
+    }
+
+    pub async fn decode_call(
+        &self,
+        chain_id: u64,
+        address: &Address,
+        call_data: &str,
+    ) -> Result<(String, Vec<ethabi::Token>), AppError> {
+        let (contract, _abi) = self.get_abi(chain_id, address).await?;
+        decode::decode_function_call(&contract, call_data)
+    }
+
+    pub async fn decode_log(
+        &self,
+        chain_id: u64,
+        address: &Address,
+        topics: &[H256],
+        data: &str,
+    ) -> Result<Log, AppError> {
+        let (contract, _abi) = self.get_abi(chain_id, address).await?;
+        decode::decode_log(&contract, topics, data)
+    }
+
+    pub async fn decode_error(
+        &self,
+        chain_id: u64,
+        address: &Address,
+        revert_data: &str,
+    ) -> Result<DecodedError, AppError> {
+        let (contract, _abi) = self.get_abi(chain_id, address).await?;
+        decode::decode_error(&contract, revert_data)
+    }
+}
+
+fn signatures_failed(key: AbiKey, e: &AppError) {
+    warn!(chain_id = key.0, contract = ?key.1, code = e.code(), error = %e, "no se pudieron registrar las firmas del ABI");
+}
+
+pub fn load_contract(abi: &Value) -> Result<Contract, AppError> {
+    let contract = Contract::load(abi.to_string().as_bytes())
+        .map_err(|e| AppError::InvalidAbi(e.to_string()))?;
+    check_param_types(&contract)?;
+    Ok(contract)
+}
+
+// Segundos unix; None si el reloj del sistema es anterior a 1970
+fn now_secs() -> Option<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_secs())
+}
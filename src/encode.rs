@@ -0,0 +1,228 @@
+use ethabi::ethereum_types::U256;
+use ethabi::{Address, Contract, Function, ParamType, Token};
+use serde_json::Value;
+
+use crate::decode::function_signature;
+use crate::decoder::load_contract;
+use crate::error::AppError;
+use crate::human_abi::{parse_human_abi, valid_fixed_bytes, valid_int_width};
+
+// Función del ABI a codificar: por nombre, o por firma si el nombre está
+// sobrecargado. La firma admite nombres de parámetros y tipos sin normalizar
+// (`transfer(address to, uint amount)`).
+pub fn find_function<'a>(contract: &'a Contract, function: &str) -> Result<&'a Function, AppError> {
+    let function = function.trim();
+    if function.contains('(') {
+        let parsed = load_contract(&parse_human_abi(function)?)?;
+        let signature = parsed
+            .functions()
+            .next()
+            .map(function_signature)
+            .ok_or_else(|| AppError::InvalidRequest(format!("'{}' no es una firma", function)))?;
+        return contract
+            .functions()
+            .find(|f| function_signature(f) == signature)
+            .ok_or(AppError::FunctionNotFound(signature));
+    }
+
+    match contract.functions_by_name(function).map(Vec::as_slice) {
+        Ok([single]) => Ok(single),
+        Ok(overloads) => Err(AppError::InvalidRequest(format!(
+            "'{}' está sobrecargada; indica la firma: {}",
+            function,
+            overloads
+                .iter()
+                .map(function_signature)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+        Err(_) => Err(AppError::FunctionNotFound(function.to_string())),
+    }
+}
+
+// Argumentos JSON como tokens del ABI: un array en el orden de los parámetros
+// o un objeto con sus nombres. Acepta lo mismo que produce `token_to_json`,
+// además de números JSON para los enteros.
+pub fn json_to_tokens(function: &Function, arguments: &Value) -> Result<Vec<Token>, AppError> {
+    let inputs = &function.inputs;
+    let values: Vec<&Value> = match arguments {
+        Value::Null if inputs.is_empty() => Vec::new(),
+        Value::Array(values) if values.len() == inputs.len() => values.iter().collect(),
+        Value::Array(values) => {
+            return Err(AppError::InvalidArguments(format!(
+                "{} espera {} argumentos y se recibieron {}",
+                function_signature(function),
+                inputs.len(),
+                values.len()
+            )))
+        }
+        Value::Object(by_name) => {
+            if let Some(unknown) = by_name
+                .keys()
+                .find(|key| !inputs.iter().any(|p| &p.name == *key))
+            {
+                return Err(AppError::InvalidArguments(format!(
+                    "{} no tiene el parámetro '{}'",
+                    function_signature(function),
+                    unknown
+                )));
+            }
+            inputs
+                .iter()
+                .enumerate()
+                .map(|(i, param)| {
+                    by_name.get(&param.name).ok_or_else(|| {
+                        AppError::InvalidArguments(format!(
+                            "falta el argumento '{}'",
+                            param_path(&param.name, i)
+                        ))
+                    })
+                })
+                .collect::<Result<_, _>>()?
+        }
+        _ => {
+            return Err(AppError::InvalidArguments(
+                "`arguments` debe ser un array o un objeto con los nombres de los parámetros"
+                    .to_string(),
+            ))
+        }
+    };
+
+    inputs
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(i, (param, value))| json_to_token(&param.kind, value, &param_path(&param.name, i)))
+        .collect::<Result<_, String>>()
+        .map_err(AppError::InvalidArguments)
+}
+
+// Call data (selector incluido) de la llamada
+pub fn encode_function_call(function: &Function, tokens: &[Token]) -> Result<Vec<u8>, AppError> {
+    function
+        .encode_input(tokens)
+        .map_err(|e| AppError::InvalidArguments(e.to_string()))
+}
+
+// Inverso de `token_to_json` para un valor del tipo `kind`; `path` identifica
+// el argumento en los errores (`orders[1].0`)
+fn json_to_token(kind: &ParamType, value: &Value, path: &str) -> Result<Token, String> {
+    let invalid =
+        |expected: &str| format!("{}: se esperaba {}, se recibió {}", path, expected, value);
+    match kind {
+        // Anchos que ethabi acepta al leer el tipo pero que Solidity no admite
+        ParamType::Int(bits) | ParamType::Uint(bits) if !valid_int_width(*bits) => {
+            Err(format!("{}: el tipo {} no es válido", path, kind))
+        }
+        ParamType::FixedBytes(len) if !valid_fixed_bytes(*len) => {
+            Err(format!("{}: el tipo {} no es válido", path, kind))
+        }
+        ParamType::Address => value
+            .as_str()
+            .and_then(|s| s.trim_start_matches("0x").parse::<Address>().ok())
+            .map(Token::Address)
+            .ok_or_else(|| invalid("una dirección")),
+        ParamType::Bool => value
+            .as_bool()
+            .map(Token::Bool)
+            .ok_or_else(|| invalid("un bool")),
+        ParamType::String => value
+            .as_str()
+            .map(|s| Token::String(s.to_string()))
+            .ok_or_else(|| invalid("un string")),
+        ParamType::Bytes => hex_bytes(value)
+            .map(Token::Bytes)
+            .ok_or_else(|| invalid("bytes en hex")),
+        ParamType::FixedBytes(len) => match hex_bytes(value) {
+            Some(bytes) if bytes.len() == *len => Ok(Token::FixedBytes(bytes)),
+            _ => Err(invalid(&format!("{} bytes en hex", len))),
+        },
+        ParamType::Uint(bits) => {
+            let value = integer(value).ok_or_else(|| invalid("un entero"))?;
+            match value {
+                (false, magnitude) if magnitude.bits() <= *bits => Ok(Token::Uint(magnitude)),
+                _ => Err(invalid(&format!("un entero entre 0 y 2^{} - 1", bits))),
+            }
+        }
+        ParamType::Int(bits) => {
+            let (negative, magnitude) = integer(value).ok_or_else(|| invalid("un entero"))?;
+            // Rango de intN: [-2^(N-1), 2^(N-1) - 1]
+            let limit = U256::one() << (bits - 1);
+            match negative {
+                false if magnitude < limit => Ok(Token::Int(magnitude)),
+                true if magnitude <= limit => {
+                    Ok(Token::Int((!magnitude).overflowing_add(U256::one()).0))
+                }
+                _ => Err(invalid(&format!("un entero de {} bits con signo", bits))),
+            }
+        }
+        ParamType::Array(inner) => {
+            let values = value.as_array().ok_or_else(|| invalid("un array"))?;
+            elements(inner, values, path).map(Token::Array)
+        }
+        ParamType::FixedArray(inner, len) => match value.as_array() {
+            Some(values) if values.len() == *len => {
+                elements(inner, values, path).map(Token::FixedArray)
+            }
+            _ => Err(invalid(&format!("un array de {} elementos", len))),
+        },
+        ParamType::Tuple(kinds) => match value.as_array() {
+            Some(values) if values.len() == kinds.len() => kinds
+                .iter()
+                .zip(values)
+                .enumerate()
+                .map(|(i, (kind, value))| json_to_token(kind, value, &format!("{}.{}", path, i)))
+                .collect::<Result<_, _>>()
+                .map(Token::Tuple),
+            _ => Err(invalid(&format!(
+                "una tupla de {} componentes",
+                kinds.len()
+            ))),
+        },
+    }
+}
+
+fn elements(inner: &ParamType, values: &[Value], path: &str) -> Result<Vec<Token>, String> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| json_to_token(inner, value, &format!("{}[{}]", path, i)))
+        .collect()
+}
+
+fn param_path(name: &str, index: usize) -> String {
+    if name.is_empty() {
+        format!("#{}", index)
+    } else {
+        name.to_string()
+    }
+}
+
+fn hex_bytes(value: &Value) -> Option<Vec<u8>> {
+    let text = value.as_str()?;
+    hex::decode(text.strip_prefix("0x").unwrap_or(text)).ok()
+}
+
+// Signo y valor absoluto de un número JSON o de un string decimal o hex (0x)
+fn integer(value: &Value) -> Option<(bool, U256)> {
+    match value {
+        Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(positive), _) => Some((false, U256::from(positive))),
+            (None, Some(negative)) => Some((true, U256::from(negative.unsigned_abs()))),
+            _ => None, // Decimales o fuera de rango: se deben enviar como string
+        },
+        Value::String(text) => {
+            let text = text.trim();
+            let (negative, digits) = match text.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, text),
+            };
+            let magnitude = match digits.strip_prefix("0x") {
+                Some(hex) => U256::from_str_radix(hex, 16).ok()?,
+                None => U256::from_dec_str(digits).ok()?,
+            };
+            Some((negative && !magnitude.is_zero(), magnitude))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,178 @@
+use thiserror::Error;
+
+// Error unificado de la aplicación. Cada variante tiene un código estable
+// (`code()`) que se devuelve en el JSON y un status HTTP (`status_code()`).
+#[derive(Debug, Clone, Error)]
+pub enum AppError {
+    #[error("Dirección de contrato inválida: {0}")]
+    InvalidAddress(String),
+    #[error("Datos hexadecimales inválidos: {0}")]
+    InvalidHex(String),
+    #[error("Datos de llamada muy cortos: se esperaban al menos 4 bytes, se recibieron {0}")]
+    CalldataTooShort(usize),
+    #[error("No se encontró función coincidente para el selector: {0}")]
+    SelectorNotFound(String),
+    #[error("Los argumentos no encajan con ninguna función del selector: {0}")]
+    ArgumentsMismatch(String),
+    #[error("El ABI no tiene la función: {0}")]
+    FunctionNotFound(String),
+    #[error("Argumentos inválidos: {0}")]
+    InvalidArguments(String),
+    #[error("No se encontró evento coincidente para el topic: {0}")]
+    EventNotFound(String),
+    #[error("Petición inválida: {0}")]
+    InvalidRequest(String),
+    #[error("ABI inválido: {0}")]
+    InvalidAbi(String),
+    #[error("El contrato no está verificado en el explorador: {0}")]
+    AbiNotVerified(String),
+    #[error("Límite de peticiones del explorador alcanzado: {0}")]
+    ExplorerRateLimited(String),
+    #[error("Error del explorador de bloques: {0}")]
+    ExplorerUnavailable(String),
+    #[error("Nodo RPC no disponible: {0}")]
+    RpcUnavailable(String),
+    #[error("LLM no configurado: {0}")]
+    LlmNotConfigured(String),
+    #[error("LLM no disponible: {0}")]
+    LlmUnavailable(String),
+    #[error("Respuesta inválida del LLM: {0}")]
+    LlmBadResponse(String),
+    #[error("Configuración del prompt inválida: {0}")]
+    PromptConfigInvalid(String),
+    #[error("Configuración del servidor inválida:\n{0}")]
+    ConfigInvalid(String),
+    #[error("API key ausente o inválida: {0}")]
+    Unauthorized(String),
+    #[error("Operación no permitida: {0}")]
+    Forbidden(String),
+    #[error("Límite de peticiones alcanzado: {0}")]
+    RateLimited(String, u64), // Segundos hasta que se puede reintentar
+    #[error("Cuota diaria agotada: {0}")]
+    QuotaExceeded(String, u64),
+    #[error("No encontrado: {0}")]
+    NotFound(String),
+    #[error("Error de almacenamiento: {0}")]
+    Storage(String),
+    #[error("Error interno: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    // Status HTTP correspondiente; disponible sin la feature `server` para
+    // que otros transportes puedan reutilizar el mismo mapeo.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            AppError::InvalidAddress(_) | AppError::InvalidHex(_) | AppError::InvalidRequest(_) => {
+                400
+            }
+            AppError::Unauthorized(_) => 401,
+            AppError::Forbidden(_) => 403,
+            AppError::AbiNotVerified(_) | AppError::NotFound(_) => 404,
+            AppError::CalldataTooShort(_)
+            | AppError::SelectorNotFound(_)
+            | AppError::ArgumentsMismatch(_)
+            | AppError::FunctionNotFound(_)
+            | AppError::InvalidArguments(_)
+            | AppError::EventNotFound(_)
+            | AppError::InvalidAbi(_) => 422,
+            AppError::ExplorerRateLimited(_)
+            | AppError::RateLimited(..)
+            | AppError::QuotaExceeded(..) => 429,
+            AppError::ExplorerUnavailable(_)
+            | AppError::RpcUnavailable(_)
+            | AppError::LlmBadResponse(_) => 502,
+            AppError::LlmNotConfigured(_) | AppError::LlmUnavailable(_) => 503,
+            AppError::PromptConfigInvalid(_)
+            | AppError::ConfigInvalid(_)
+            | AppError::Storage(_)
+            | AppError::Internal(_) => 500,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::InvalidAddress(_) => "INVALID_ADDRESS",
+            AppError::InvalidHex(_) => "INVALID_HEX",
+            AppError::CalldataTooShort(_) => "CALLDATA_TOO_SHORT",
+            AppError::SelectorNotFound(_) => "SELECTOR_NOT_FOUND",
+            AppError::ArgumentsMismatch(_) => "ARGUMENTS_MISMATCH",
+            AppError::FunctionNotFound(_) => "FUNCTION_NOT_FOUND",
+            AppError::InvalidArguments(_) => "INVALID_ARGUMENTS",
+            AppError::EventNotFound(_) => "EVENT_NOT_FOUND",
+            AppError::InvalidRequest(_) => "INVALID_REQUEST",
+            AppError::InvalidAbi(_) => "INVALID_ABI",
+            AppError::AbiNotVerified(_) => "ABI_NOT_VERIFIED",
+            AppError::ExplorerRateLimited(_) => "EXPLORER_RATE_LIMITED",
+            AppError::ExplorerUnavailable(_) => "EXPLORER_UNAVAILABLE",
+            AppError::RpcUnavailable(_) => "RPC_UNAVAILABLE",
+            AppError::LlmNotConfigured(_) => "LLM_NOT_CONFIGURED",
+            AppError::LlmUnavailable(_) => "LLM_UNAVAILABLE",
+            AppError::LlmBadResponse(_) => "LLM_BAD_RESPONSE",
+            AppError::PromptConfigInvalid(_) => "PROMPT_CONFIG_INVALID",
+            AppError::ConfigInvalid(_) => "CONFIG_INVALID",
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::RateLimited(..) => "RATE_LIMITED",
+            AppError::QuotaExceeded(..) => "QUOTA_EXCEEDED",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Storage(_) => "STORAGE_ERROR",
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    // Fallos temporales del explorador o del LLM (red, timeout, 429, 5xx,
+    // límite del explorador) que merece la pena reintentar
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            AppError::ExplorerRateLimited(_)
+                | AppError::ExplorerUnavailable(_)
+                | AppError::RpcUnavailable(_)
+                | AppError::LlmUnavailable(_)
+        )
+    }
+
+    // Valor de la cabecera Retry-After para los errores de límite propios
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            AppError::RateLimited(_, secs) | AppError::QuotaExceeded(_, secs) => Some(*secs),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl actix_web::ResponseError for AppError {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        actix_web::http::StatusCode::from_u16(self.http_status())
+            .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> actix_web::HttpResponse {
+        let mut response = actix_web::HttpResponse::build(self.status_code());
+        if let Some(secs) = self.retry_after() {
+            response.insert_header((actix_web::http::header::RETRY_AFTER, secs.to_string()));
+        }
+        response.json(crate::ErrorResponse {
+            status: "error".to_string(),
+            code: self.code().to_string(),
+            message: self.to_string(),
+            details: None,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Storage(e.to_string())
+    }
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for AppError {
+    fn from(e: redis::RedisError) -> Self {
+        AppError::Storage(e.to_string())
+    }
+}
@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+use ethabi::{Address, Contract};
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use std::env;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::decoder::{AbiProvider, Decoder, FetchedAbi};
+use crate::error::AppError;
+use crate::store::{abi_cache_from_config, SignatureDb};
+use crate::upstream::{Upstream, Upstreams};
+use crate::{AppConfig, ChainConfig};
+
+// Usa `getsourcecode` en vez de `getabi`: además del ABI trae el nombre del
+// contrato, la versión del compilador y, si es un proxy, su implementación.
+pub async fn fetch_abi_from_explorer(
+    upstream: &Upstream,
+    chain: &ChainConfig,
+    contract_address: &str,
+) -> Result<FetchedAbi, AppError> {
+    let api_key = chain
+        .api_key_env
+        .as_ref()
+        .and_then(|name| env::var(name).ok())
+        .unwrap_or_default();
+
+    let mut query = vec![
+        ("module", "contract"),
+        ("action", "getsourcecode"),
+        ("address", contract_address),
+    ];
+    if !api_key.is_empty() {
+        query.push(("apikey", &api_key));
+    }
+
+    info!(chain = %chain.name, contract = %contract_address, "solicitando ABI al explorador");
+    let query = &query;
+    let json = upstream
+        .execute(|| async move {
+            let response = upstream
+                .request(Method::GET, &chain.explorer_api_url)
+                .query(query)
+                .send()
+                .await
+                .map_err(|e| AppError::ExplorerUnavailable(e.to_string()))?;
+            let status = response.status();
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                return Err(AppError::ExplorerRateLimited(format!(
+                    "HTTP status {}",
+                    status
+                )));
+            }
+            if !status.is_success() {
+                return Err(AppError::ExplorerUnavailable(format!(
+                    "HTTP status {}",
+                    status
+                )));
+            }
+            let json: Value = response
+                .json()
+                .await
+                .map_err(|e| AppError::ExplorerUnavailable(e.to_string()))?;
+            if json["status"] == "1" {
+                Ok(json)
+            } else {
+                // Los límites de peticiones se reintentan dentro de `execute`
+                Err(explorer_error(&json, chain))
+            }
+        })
+        .await?;
+
+    let source = &json["result"][0];
+    let abi_string = source["ABI"]
+        .as_str()
+        .ok_or_else(|| AppError::ExplorerUnavailable("Respuesta sin ABI".to_string()))?;
+    // Los contratos sin verificar llegan con status "1" y un texto en "ABI"
+    if abi_string.to_lowercase().contains("not verified") {
+        return Err(AppError::AbiNotVerified(format!(
+            "{}. Asegúrate de que el contrato esté verificado en {}.",
+            abi_string, chain.name
+        )));
+    }
+    let abi = serde_json::from_str(abi_string).map_err(|e| AppError::InvalidAbi(e.to_string()))?;
+
+    let text = |field: &str| {
+        source[field]
+            .as_str()
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let implementation = if source["Proxy"] == "1" {
+        text("Implementation").and_then(|value| value.parse().ok())
+    } else {
+        None
+    };
+    Ok(FetchedAbi {
+        abi,
+        contract_name: text("ContractName"),
+        compiler_version: text("CompilerVersion"),
+        implementation,
+    })
+}
+
+// Clasifica una respuesta de error de la API del explorador (status "0").
+// El detalle útil viene en "result"; "message" suele ser solo "NOTOK".
+fn explorer_error(json: &Value, chain: &ChainConfig) -> AppError {
+    let message = json["message"].as_str().unwrap_or("Error desconocido");
+    let result = json["result"].as_str().unwrap_or(message);
+    let lowered = result.to_lowercase();
+
+    if lowered.contains("rate limit") {
+        AppError::ExplorerRateLimited(result.to_string())
+    } else if lowered.contains("not verified") {
+        AppError::AbiNotVerified(format!(
+            "{}. Asegúrate de que el contrato esté verificado en {}.",
+            result, chain.name
+        ))
+    } else if lowered.contains("invalid address") {
+        AppError::InvalidAddress(result.to_string())
+    } else {
+        AppError::ExplorerUnavailable(format!("{}: {}", message, result))
+    }
+}
+
+// Proveedor de ABIs basado en los exploradores del registro de cadenas
+pub struct ExplorerAbiProvider {
+    chains: Vec<ChainConfig>,
+    upstream: Arc<Upstream>,
+}
+
+impl ExplorerAbiProvider {
+    pub fn new(chains: Vec<ChainConfig>, upstream: Arc<Upstream>) -> Self {
+        ExplorerAbiProvider { chains, upstream }
+    }
+
+    pub fn from_config(config: &AppConfig, upstreams: &Upstreams) -> Self {
+        Self::new(config.chains.clone(), upstreams.explorer.clone())
+    }
+}
+
+#[async_trait]
+impl AbiProvider for ExplorerAbiProvider {
+    async fn fetch_abi(&self, chain_id: u64, address: &Address) -> Result<FetchedAbi, AppError> {
+        let chain = self
+            .chains
+            .iter()
+            .find(|chain| chain.chain_id == chain_id)
+            .ok_or_else(|| {
+                AppError::InvalidRequest(format!("Cadena no soportada: {}", chain_id))
+            })?;
+        let address_string = format!("{:?}", address);
+        match fetch_abi_from_explorer(&self.upstream, chain, &address_string).await {
+            Ok(fetched) => {
+                info!(
+                    chain = %chain.name,
+                    contract_name = fetched.contract_name.as_deref(),
+                    implementation = ?fetched.implementation,
+                    "ABI obtenido del explorador"
+                );
+                Ok(fetched)
+            }
+            Err(e) => {
+                warn!(chain = %chain.name, code = e.code(), error = %e, "el explorador no devolvió el ABI");
+                Err(e)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "explorer"
+    }
+}
+
+// Decoder configurado como el servidor: caché del backend configurado
+// (por defecto la carpeta `ABI/`) más el explorador
+pub fn decoder_from_config(config: &AppConfig, upstreams: &Upstreams) -> Result<Decoder, AppError> {
+    Ok(Decoder::new(abi_cache_from_config(config)?)
+        .with_provider(ExplorerAbiProvider::from_config(config, upstreams))
+        .with_ttl(config.abi_ttl())
+        .with_signatures(SignatureDb::from_config(config)?))
+}
+
+// Para usos puntuales (CLI): crea su propio cliente HTTP
+pub async fn get_or_fetch_abi(
+    config: &AppConfig,
+    chain: &ChainConfig,
+    contract_address: &Address,
+) -> Result<(Contract, Value), AppError> {
+    decoder_from_config(config, &Upstreams::from_config(config)?)?
+        .get_abi(chain.chain_id, contract_address)
+        .await
+}
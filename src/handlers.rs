@@ -1,336 +1,1199 @@
-use actix_web::{web, HttpResponse, Responder};
-use ethers::types::Address;
-use log::{error, info, warn};
-use reqwest::{
-    header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
-    Client,
+use actix_web::{
+    guard,
+    http::{header, Method, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse, Resource, Responder, ResponseError, Route,
 };
+use chrono::DateTime;
+use ethabi::{Address, Contract, Function};
 use serde_json::{json, Value};
-use std::env;
-use url::Url;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+use utoipa::OpenApi;
+use utoipa_redoc::{Redoc, Servable};
 
-use crate::abi::get_or_fetch_abi;
+use crate::auth::{require_admin, ApiKeyName};
+use crate::bundle::{export_bundle, import_bundle, AbiBundle};
 use crate::config::load_prompt_config;
-use crate::decode::decode_function_call;
-use crate::{AnalysisRequest, AnalysisResponse, DecodeRequest, DecodeResponse};
+use crate::decode::{decode_function, decode_with_candidates, function_signature, token_to_json};
+use crate::decoder::{load_contract, CachedAbi, Decoder, SOURCE_UPLOAD};
+use crate::encode::{encode_function_call, find_function, json_to_tokens};
+use crate::error::AppError;
+use crate::history::{page_bounds, HistoryStore, NewHistoryEntry};
+use crate::human_abi::normalize_abi;
+use crate::llm::{self, AnalysisCache};
+use crate::metrics::Metrics;
+use crate::openapi::ApiDoc;
+use crate::strict::check_encoding;
+use crate::summary::{annotate_arguments, summarize, token_amounts, uses_token_metadata};
+use crate::token::TokenResolver;
+use crate::upstream::Upstreams;
+use crate::{
+    AbiCacheEntry, AbiDetailResponse, AbiImportQuery, AbiImportResponse, AbiListResponse,
+    AbiUploadRequest, AnalysisRequest, AnalysisResponse, AppConfig, ChainConfig, DecodeRequest,
+    DecodeResponse, EncodeRequest, EncodeResponse, ErrorResponse, HealthCheck, HealthResponse,
+    HistoryEntry, HistoryListResponse, HistoryQuery, InlineAbi, TokenMetadata,
+};
+
+// Tamaño máximo de un bundle en POST /abis/import
+const BUNDLE_SIZE_LIMIT: usize = 64 * 1024 * 1024;
 
-pub async fn decode_handler(req: web::Json<DecodeRequest>) -> impl Responder {
-    info!(
-        "📥 Petición recibida en /decode - Contrato: {}",
-        req.contract_address
+// Registra los endpoints de `endpoints()` y la documentación (/openapi.json y
+// /docs con Redoc), que no forma parte de la especificación.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(
+        web::JsonConfig::default()
+            .error_handler(|err, _req| AppError::InvalidRequest(err.to_string()).into()),
+    )
+    .app_data(
+        web::PathConfig::default()
+            .error_handler(|err, _req| AppError::InvalidRequest(err.to_string()).into()),
     );
+    for (_, _, resource) in endpoints() {
+        cfg.service(resource);
+    }
+    cfg.route("/openapi.json", web::get().to(openapi_handler))
+        .service(Redoc::with_url("/docs", ApiDoc::openapi()));
+}
 
-    let contract_address_result = req.contract_address.parse::<Address>();
-    let contract_address = match contract_address_result {
-        Ok(addr) => addr,
-        Err(e) => {
-            warn!(
-                "❌ Dirección de contrato inválida: {} - Error: {}",
-                req.contract_address, e
-            );
-            return HttpResponse::BadRequest().json(DecodeResponse {
-                status: "error".to_string(),
-                function_name: None,
-                arguments: None,
-                message: Some(format!("Dirección de contrato inválida: {}", e)),
-                details: None,
-                abi: None,
-            });
-        }
-    };
+// Ruta y método de cada endpoint de la API. tests/openapi.rs comprueba que
+// coinciden con las operaciones de `openapi::ApiDoc` en ambos sentidos.
+pub fn routes() -> Vec<(&'static str, Method)> {
+    endpoints()
+        .into_iter()
+        .map(|(path, method, _)| (path, method))
+        .collect()
+}
 
-    let (contract, abi) = match get_or_fetch_abi(&contract_address).await {
-        Ok((c, a)) => (c, a),
-        Err(e) => {
-            error!("❌ Error al obtener ABI para {}: {}", contract_address, e);
-            return HttpResponse::InternalServerError().json(DecodeResponse {
-                status: "error".to_string(),
-                function_name: None,
-                arguments: None,
-                message: Some("Error al obtener o cargar el ABI".to_string()),
-                details: Some(e.to_string()),
-                abi: None,
-            });
+fn endpoints() -> Vec<(&'static str, Method, Resource)> {
+    let (path, method, import) =
+        endpoint("/abis/import", Method::POST, web::to(abi_import_handler));
+    let import = import.app_data(
+        web::JsonConfig::default()
+            .limit(BUNDLE_SIZE_LIMIT)
+            .error_handler(|err, _req| AppError::InvalidRequest(err.to_string()).into()),
+    );
+    vec![
+        endpoint("/decode", Method::POST, web::to(decode_handler)),
+        endpoint("/analysis", Method::POST, web::to(analysis_handler)),
+        endpoint("/encode", Method::POST, web::to(encode_handler)),
+        endpoint("/history", Method::GET, web::to(history_list_handler)),
+        endpoint("/history/{id}", Method::GET, web::to(history_entry_handler)),
+        endpoint("/abis", Method::GET, web::to(abi_list_handler)),
+        endpoint("/abis/export", Method::GET, web::to(abi_export_handler)),
+        (path, method, import),
+        endpoint(
+            "/abis/{chain_id}/{address}",
+            Method::GET,
+            web::to(abi_get_handler),
+        ),
+        endpoint(
+            "/abis/{chain_id}/{address}",
+            Method::PUT,
+            web::to(abi_upload_handler),
+        ),
+        endpoint(
+            "/abis/{chain_id}/{address}",
+            Method::DELETE,
+            web::to(abi_delete_handler),
+        ),
+        endpoint(
+            "/abis/{chain_id}/{address}/refresh",
+            Method::POST,
+            web::to(abi_refresh_handler),
+        ),
+        endpoint("/healthz", Method::GET, web::to(health_handler)),
+        endpoint("/readyz", Method::GET, web::to(readiness_handler)),
+        endpoint("/metrics", Method::GET, web::to(metrics_handler)),
+    ]
+}
+
+// Un recurso por método, con el guard en el recurso (como `ServiceConfig::route`)
+// para que los demás métodos de la misma ruta sigan resolviéndose
+fn endpoint(path: &'static str, method: Method, route: Route) -> (&'static str, Method, Resource) {
+    let resource = web::resource(path)
+        .guard(guard::Method(method.clone()))
+        .route(route);
+    (path, method, resource)
+}
+
+pub async fn openapi_handler() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+#[utoipa::path(
+    post,
+    path = "/decode",
+    tag = "decode",
+    summary = "Decodifica datos de llamada con el ABI del contrato",
+    description = "Con `abi` (array JSON, firmas legibles como \
+                   `function transfer(address to, uint256 amount)` o una interfaz de Solidity) \
+                   o `signature` en la petición se usa ese ABI en lugar del de la caché o el \
+                   explorador, y `contract_address` es opcional. `save_abi` guarda `abi` en \
+                   caché para `contract_address`; con autenticación requiere una clave `admin`. \
+                   Con `strict` los argumentos se recodifican y `findings` lista las \
+                   diferencias con `call_data`: relleno sucio, offsets o longitudes no \
+                   canónicos y bytes sobrantes.",
+    request_body = DecodeRequest,
+    responses(
+        (status = 200, description = "Llamada decodificada", body = DecodeResponse),
+        (status = 403, description = "`save_abi` sin clave de administración", body = ErrorResponse),
+        (status = "4XX", description = "Petición inválida, ABI no verificado, selector desconocido o argumentos que no encajan", body = DecodeResponse),
+        (status = "5XX", description = "Explorador no disponible o error interno", body = DecodeResponse),
+    )
+)]
+pub async fn decode_handler(
+    http_req: HttpRequest,
+    req: web::Json<DecodeRequest>,
+    config: web::Data<AppConfig>,
+    decoder: web::Data<Decoder>,
+    history: web::Data<HistoryStore>,
+) -> impl Responder {
+    let started = Instant::now();
+    let mut entry = NewHistoryEntry {
+        endpoint: "decode".to_string(),
+        caller: caller_of(&http_req),
+        contract_address: req.contract_address.clone(),
+        call_data: req.call_data.clone(),
+        request: json!(&*req),
+        ..Default::default()
+    };
+    // Guardar el ABI en caché es una operación de administración, como PUT /abis
+    if req.inline.save_abi {
+        if let Err(e) = require_admin(&http_req, &config.auth) {
+            warn!(code = e.code(), error = %e, "petición rechazada");
+            record_rejected(&history, entry, started, &e).await;
+            return e.error_response();
         }
+    }
+    // Opcional: sin `TokenResolver` en app_data los resúmenes van en unidades base
+    let tokens = http_req
+        .app_data::<web::Data<TokenResolver>>()
+        .map(|resolver| resolver.get_ref());
+    let (status, response) = decode(&req, &config, &decoder, tokens).await;
+
+    entry.http_status = status.as_u16();
+    entry.status = response.status.clone();
+    entry.function_name = response.function_name.clone();
+    entry.arguments = response.arguments.clone();
+    entry.latency_ms = started.elapsed().as_millis() as u64;
+    entry.message = response.message.clone();
+    entry.details = response.details.clone();
+    record_history(&history, entry).await;
+
+    HttpResponse::build(status).json(response)
+}
+
+async fn decode(
+    req: &DecodeRequest,
+    config: &AppConfig,
+    decoder: &web::Data<Decoder>,
+    tokens: Option<&TokenResolver>,
+) -> (StatusCode, DecodeResponse) {
+    info!(contract = %req.contract_address, chain_id = ?req.chain_id, "decodificación solicitada");
+
+    let (contract, abi) = match resolve_abi(
+        &req.contract_address,
+        req.chain_id,
+        &req.inline,
+        config,
+        decoder,
+    )
+    .await
+    {
+        Ok(resolved) => resolved,
+        Err((message, e)) => return decode_error(message, &e),
     };
 
-    match decode_function_call(&contract, &req.call_data) {
-        Ok((name, args)) => {
+    let (decoded, candidates) =
+        info_span!("decode").in_scope(|| decode_with_candidates(&contract, &req.call_data));
+    if let Some(candidates) = &candidates {
+        warn!(
+            candidates = candidates.len(),
+            decoded = candidates.iter().filter(|c| c.decoded).count(),
+            "selector con varios candidatos o argumentos que no encajan"
+        );
+    }
+
+    match decoded {
+        Ok((function, args)) => {
+            let findings = if req.strict {
+                match check_encoding(function, &args, &req.call_data) {
+                    Ok(findings) => Some(findings),
+                    Err(e) => return decode_error("Error en la validación estricta", &e),
+                }
+            } else {
+                None
+            };
+            if let Some(findings) = findings.as_ref().filter(|f| !f.is_empty()) {
+                warn!(
+                    findings = findings.len(),
+                    "call data con codificación no canónica"
+                );
+            }
+            let token = token_metadata(
+                function,
+                &req.contract_address,
+                req.chain_id,
+                config,
+                tokens,
+            )
+            .await;
+            let summary = summarize(function, &args, token.as_ref());
+            let amounts = token_amounts(function, &args, token.as_ref());
+            let name = function.name.clone();
             let args_str: Vec<String> = args.into_iter().map(|arg| format!("{:?}", arg)).collect();
-            info!(
-                "✅ Decodificación exitosa - Función: {}, Argumentos: {:?}",
-                name, args_str
-            );
-            HttpResponse::Ok().json(DecodeResponse {
-                status: "success".to_string(),
-                function_name: Some(name),
-                arguments: Some(args_str),
-                message: None,
-                details: None,
-                abi: Some(abi),
-            })
+            info!(function = %name, arguments = args_str.len(), "decodificación completada");
+            (
+                StatusCode::OK,
+                DecodeResponse {
+                    status: "success".to_string(),
+                    function_name: Some(name),
+                    arguments: Some(args_str),
+                    summary,
+                    token,
+                    amounts: Some(amounts).filter(|amounts| !amounts.is_empty()),
+                    message: None,
+                    details: None,
+                    code: None,
+                    abi: Some(abi),
+                    candidates,
+                    findings,
+                },
+            )
         }
         Err(e) => {
-            error!("❌ Error al decodificar call data: {}", e);
-            HttpResponse::InternalServerError().json(DecodeResponse {
-                status: "error".to_string(),
-                function_name: None,
-                arguments: None,
-                message: Some("Error al decodificar los datos de llamada".to_string()),
-                details: Some(e.to_string()),
-                abi: None,
-            })
+            warn!(code = e.code(), error = %e, "no se pudo decodificar call data");
+            let (status, mut response) =
+                decode_error("Error al decodificar los datos de llamada", &e);
+            response.candidates = candidates;
+            // Selector ausente del ABI: se sugieren las firmas vistas en otros contratos
+            if matches!(e, AppError::SelectorNotFound(_)) {
+                let selector: String = req
+                    .call_data
+                    .trim_start_matches("0x")
+                    .chars()
+                    .take(8)
+                    .collect();
+                let lookup = selector.clone();
+                match run_blocking(decoder, move |decoder| decoder.lookup_signatures(&lookup)).await
+                {
+                    Ok(known) if !known.is_empty() => {
+                        response.details = Some(format!(
+                            "{}. Firmas conocidas para 0x{}: {}",
+                            e,
+                            selector,
+                            known.join(", ")
+                        ));
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        warn!(code = e.code(), error = %e, "no se pudo consultar la base de firmas")
+                    }
+                }
+            }
+            (status, response)
         }
     }
 }
 
-pub async fn analysis_handler(req: web::Json<AnalysisRequest>) -> impl Responder {
-    info!(
-        "📥 Petición recibida en /analysis - Contrato: {}",
-        req.contract_address
-    );
-
-    let deepseek_api_key = match env::var("DEEPSEEK_API_KEY") {
-        Ok(key) => key,
-        Err(_) => {
-            error!(
-                "❌ DEEPSEEK_API_KEY no configurada para análisis de contrato: {}",
-                req.contract_address
-            );
-            return HttpResponse::InternalServerError().json(AnalysisResponse {
-                status: "error".to_string(),
-                function_name: None,
-                arguments: None,
-                risk_level: None,
-                explanation: None,
-                message: Some("DEEPSEEK_API_KEY no configurada".to_string()),
-                details: Some("Asegúrate de configurar la variable de entorno DEEPSEEK_API_KEY en tu archivo .env".to_string()),
-            });
-        }
+#[utoipa::path(
+    post,
+    path = "/analysis",
+    tag = "analysis",
+    summary = "Decodifica la llamada y evalúa su riesgo con el LLM",
+    description = "Admite `abi`, `signature` y `save_abi` igual que `/decode`.",
+    request_body = AnalysisRequest,
+    responses(
+        (status = 200, description = "Análisis de riesgo completado", body = AnalysisResponse),
+        (status = 403, description = "`save_abi` sin clave de administración", body = ErrorResponse),
+        (status = "4XX", description = "Petición inválida o fallo de decodificación", body = AnalysisResponse),
+        (status = "5XX", description = "LLM o explorador no disponibles", body = AnalysisResponse),
+    )
+)]
+pub async fn analysis_handler(
+    http_req: HttpRequest,
+    req: web::Json<AnalysisRequest>,
+    config: web::Data<AppConfig>,
+    decoder: web::Data<Decoder>,
+    history: web::Data<HistoryStore>,
+    upstreams: web::Data<Upstreams>,
+    metrics: web::Data<Metrics>,
+) -> impl Responder {
+    let started = Instant::now();
+    let mut entry = NewHistoryEntry {
+        endpoint: "analysis".to_string(),
+        caller: caller_of(&http_req),
+        contract_address: req.contract_address.clone(),
+        call_data: req.call_data.clone(),
+        request: json!(&*req),
+        ..Default::default()
     };
-
-    // Parse contract address
-    let contract_address = match req.contract_address.parse::<Address>() {
-        Ok(addr) => addr,
-        Err(e) => {
-            warn!(
-                "❌ Dirección de contrato inválida en análisis: {} - Error: {}",
-                req.contract_address, e
-            );
-            return HttpResponse::BadRequest().json(AnalysisResponse {
-                status: "error".to_string(),
-                function_name: None,
-                arguments: None,
-                risk_level: None,
-                explanation: None,
-                message: Some(format!("Dirección de contrato inválida: {}", e)),
-                details: None,
-            });
+    // Guardar el ABI en caché es una operación de administración, como PUT /abis
+    if req.inline.save_abi {
+        if let Err(e) = require_admin(&http_req, &config.auth) {
+            warn!(code = e.code(), error = %e, "petición rechazada");
+            record_rejected(&history, entry, started, &e).await;
+            return e.error_response();
         }
-    };
+    }
 
-    // Get or fetch ABI
-    let (contract, _abi) = match get_or_fetch_abi(&contract_address).await {
-        Ok((c, a)) => (c, a),
-        Err(e) => {
-            error!(
-                "❌ Error al obtener ABI para análisis de {}: {}",
-                contract_address, e
-            );
-            return HttpResponse::InternalServerError().json(AnalysisResponse {
-                status: "error".to_string(),
-                function_name: None,
-                arguments: None,
-                risk_level: None,
-                explanation: None,
-                message: Some("Error al obtener o cargar el ABI".to_string()),
-                details: Some(e.to_string()),
-            });
-        }
+    let (status, response) = analyze(
+        &http_req, &req, &config, &decoder, &upstreams, &metrics, &mut entry,
+    )
+    .await;
+
+    entry.http_status = status.as_u16();
+    entry.status = response.status.clone();
+    entry.function_name = response.function_name.clone();
+    entry.arguments = response.arguments.clone();
+    entry.risk_level = response.risk_level.clone();
+    entry.explanation = response.explanation.clone();
+    entry.latency_ms = started.elapsed().as_millis() as u64;
+    entry.message = response.message.clone();
+    entry.details = response.details.clone();
+    record_history(&history, entry).await;
+
+    HttpResponse::build(status).json(response)
+}
+
+async fn analyze(
+    http_req: &HttpRequest,
+    req: &AnalysisRequest,
+    config: &AppConfig,
+    decoder: &web::Data<Decoder>,
+    upstreams: &Upstreams,
+    metrics: &Metrics,
+    entry: &mut NewHistoryEntry,
+) -> (StatusCode, AnalysisResponse) {
+    // Opcional: sin `AnalysisCache` en app_data siempre se consulta al LLM
+    let analysis_cache = http_req
+        .app_data::<web::Data<AnalysisCache>>()
+        .map(|cache| cache.get_ref());
+    let tokens = http_req
+        .app_data::<web::Data<TokenResolver>>()
+        .map(|resolver| resolver.get_ref());
+    info!(contract = %req.contract_address, chain_id = ?req.chain_id, "análisis solicitado");
+
+    if let Err(e) = llm::api_key(config) {
+        error!(api_key_env = %config.llm.api_key_env, "API key del LLM no configurada");
+        return analysis_error(
+            &format!("{} no configurada", config.llm.api_key_env),
+            &e,
+            None,
+            None,
+        );
+    }
+
+    let (contract, _abi) = match resolve_abi(
+        &req.contract_address,
+        req.chain_id,
+        &req.inline,
+        config,
+        decoder,
+    )
+    .await
+    {
+        Ok(resolved) => resolved,
+        Err((message, e)) => return analysis_error(message, &e, None, None),
     };
 
-    // Decode function call
-    let (function_name, arguments) = match decode_function_call(&contract, &req.call_data) {
-        Ok((name, args)) => {
+    let (function_name, arguments, summary, token, amounts) = match info_span!("decode")
+        .in_scope(|| decode_function(&contract, &req.call_data))
+    {
+        Ok((function, args)) => {
+            let token = token_metadata(
+                function,
+                &req.contract_address,
+                req.chain_id,
+                config,
+                tokens,
+            )
+            .await;
+            let summary = summarize(function, &args, token.as_ref());
+            let amounts = token_amounts(function, &args, token.as_ref());
             let args_str: Vec<String> = args.into_iter().map(|arg| format!("{:?}", arg)).collect();
-            (name, args_str)
+            (function.name.clone(), args_str, summary, token, amounts)
         }
         Err(e) => {
-            error!("❌ Error al decodificar call data en análisis: {}", e);
-            return HttpResponse::InternalServerError().json(AnalysisResponse {
-                status: "error".to_string(),
-                function_name: None,
-                arguments: None,
-                risk_level: None,
-                explanation: None,
-                message: Some("Error al decodificar los datos de llamada".to_string()),
-                details: Some(e.to_string()),
-            });
+            warn!(code = e.code(), error = %e, "no se pudo decodificar call data");
+            return analysis_error("Error al decodificar los datos de llamada", &e, None, None);
         }
     };
 
     // Cargar configuración del prompt
-    let prompt_config = match load_prompt_config() {
+    let prompt_config = match load_prompt_config(config.storage.prompt_config.as_deref()) {
         Ok(config) => config,
         Err(e) => {
-            error!("❌ Error al cargar configuración del prompt: {}", e);
-            return HttpResponse::InternalServerError().json(AnalysisResponse {
-                status: "error".to_string(),
-                function_name: Some(function_name),
-                arguments: Some(arguments),
-                risk_level: None,
-                explanation: None,
-                message: Some("Error al cargar la configuración del prompt".to_string()),
-                details: Some(e.to_string()),
-            });
+            error!(code = e.code(), error = %e, "configuración del prompt inválida");
+            return analysis_error(
+                "Error al cargar la configuración del prompt",
+                &e,
+                Some(function_name),
+                Some(arguments),
+            );
         }
     };
 
-    let api_url = match Url::parse("https://api.deepseek.com/chat/completions") {
-        Ok(url) => url,
-        Err(e) => {
-            error!("❌ Error al construir URL de API DeepSeek: {}", e);
-            return HttpResponse::InternalServerError().json(AnalysisResponse {
-                status: "error".to_string(),
+    let prompt = llm::build_prompt(
+        config,
+        &prompt_config,
+        &req.contract_address,
+        &function_name,
+        &annotate_arguments(&arguments, &amounts),
+        summary.as_deref(),
+    );
+    let amounts = Some(amounts).filter(|amounts| !amounts.is_empty());
+    entry.model = Some(prompt.model.clone());
+    entry.prompt_hash = Some(prompt.prompt_hash.clone());
+
+    let cached = match analysis_cache {
+        Some(cache) => cache.get(&prompt).await,
+        None => None,
+    };
+    if let Some(verdict) = cached {
+        metrics.record_risk_level(verdict.risk_level.as_deref());
+        info!(function = %function_name, risk_level = ?verdict.risk_level, "análisis servido desde la caché");
+        return (
+            StatusCode::OK,
+            AnalysisResponse {
+                status: "success".to_string(),
                 function_name: Some(function_name),
                 arguments: Some(arguments),
-                risk_level: None,
-                explanation: None,
-                message: Some("Error interno al construir la URL de la API".to_string()),
-                details: Some(e.to_string()),
+                risk_level: verdict.risk_level,
+                explanation: verdict.explanation,
+                token,
+                amounts,
+                message: Some("Análisis de riesgo completado (resultado en caché)".to_string()),
+                details: None,
+                code: None,
+            },
+        );
+    }
+
+    let llm_started = Instant::now();
+    let verdict = llm::request_verdict(&upstreams.llm, config, &prompt_config, &prompt)
+        .instrument(info_span!("llm", model = %prompt.model, prompt_hash = %prompt.prompt_hash))
+        .await;
+    metrics
+        .llm_latency
+        .observe(llm_started.elapsed().as_secs_f64());
+
+    match verdict {
+        Ok(verdict) => {
+            if let Some(cache) = analysis_cache {
+                cache.put(&prompt, &verdict).await;
+            }
+            metrics.record_risk_level(verdict.risk_level.as_deref());
+            for (kind, tokens) in [
+                ("prompt", verdict.prompt_tokens),
+                ("completion", verdict.completion_tokens),
+            ] {
+                if let Some(tokens) = tokens {
+                    metrics.llm_tokens.with_label_values(&[kind]).inc_by(tokens);
+                }
+            }
+            info!(
+                function = %function_name,
+                risk_level = ?verdict.risk_level,
+                "análisis completado"
+            );
+            (
+                StatusCode::OK,
+                AnalysisResponse {
+                    status: "success".to_string(),
+                    function_name: Some(function_name),
+                    arguments: Some(arguments),
+                    risk_level: verdict.risk_level,
+                    explanation: verdict.explanation,
+                    token,
+                    amounts,
+                    message: Some("Análisis de riesgo completado".to_string()),
+                    details: None,
+                    code: None,
+                },
+            )
+        }
+        Err(e) => {
+            error!(code = e.code(), error = %e, "el análisis con el LLM falló");
+            analysis_error(
+                "Error al obtener el análisis de riesgo del LLM",
+                &e,
+                Some(function_name),
+                Some(arguments),
+            )
+        }
+    }
+}
+
+// ABI con el que se decodifica: el de la petición si trae `abi` o
+// `signature` (y con `save_abi` se guarda en caché), o si no el de la
+// caché/explorador para `contract_address`
+async fn resolve_abi(
+    contract_address: &str,
+    chain_id: Option<u64>,
+    inline: &InlineAbi,
+    config: &AppConfig,
+    decoder: &web::Data<Decoder>,
+) -> Result<(Contract, Value), (&'static str, AppError)> {
+    let parsed = info_span!("abi_parse").in_scope(|| -> Result<_, AppError> {
+        let abi = inline.parse()?;
+        abi.map(|abi| Ok((load_contract(&abi)?, abi))).transpose()
+    });
+    let inline_abi = parsed.map_err(|e| {
+        warn!(code = e.code(), error = %e, "ABI de la petición inválido");
+        ("ABI de la petición inválido", e)
+    })?;
+
+    let Some((contract, abi)) = inline_abi else {
+        let (contract_address, chain) = info_span!("parse")
+            .in_scope(|| parse_target(contract_address, chain_id, config))
+            .inspect_err(|(message, e)| warn!(code = e.code(), error = %e, "{}", message))?;
+        return decoder
+            .get_abi(chain.chain_id, &contract_address)
+            .instrument(
+                info_span!("abi_fetch", chain_id = chain.chain_id, contract = ?contract_address),
+            )
+            .await
+            .map_err(|e| {
+                error!(code = e.code(), error = %e, "no se pudo obtener el ABI");
+                ("Error al obtener o cargar el ABI", e)
             });
+    };
+
+    if inline.save_abi {
+        // Una firma suelta no sustituye al ABI completo del contrato
+        if inline.abi.is_none() {
+            return Err((
+                "No se pudo guardar el ABI en caché",
+                AppError::InvalidRequest("`save_abi` requiere `abi`".to_string()),
+            ));
+        }
+        let (contract_address, chain) = parse_target(contract_address, chain_id, config)?;
+        let (chain_id, saved) = (chain.chain_id, abi.clone());
+        run_blocking(decoder, move |decoder| {
+            decoder.store_abi(chain_id, &contract_address, saved, SOURCE_UPLOAD)
+        })
+        .await
+        .map_err(|e| {
+            error!(code = e.code(), error = %e, "no se pudo guardar el ABI de la petición");
+            ("No se pudo guardar el ABI en caché", e)
+        })?;
+    }
+    Ok((contract, abi))
+}
+
+// Metadatos del contrato llamado, leídos por RPC solo si la firma lleva
+// importes o ids de su token. Un fallo solo se registra: el resumen sale en
+// unidades base y sin importes anotados.
+async fn token_metadata(
+    function: &Function,
+    contract_address: &str,
+    chain_id: Option<u64>,
+    config: &AppConfig,
+    tokens: Option<&TokenResolver>,
+) -> Option<TokenMetadata> {
+    let tokens = tokens.filter(|_| uses_token_metadata(function))?;
+    let (address, chain) = parse_target(contract_address, chain_id, config).ok()?;
+    match tokens
+        .resolve(chain.chain_id, &address)
+        .instrument(info_span!("token_metadata", chain_id = chain.chain_id))
+        .await
+    {
+        Ok(found) => found,
+        Err(e) => {
+            warn!(code = e.code(), error = %e, "no se pudieron leer los metadatos del token");
+            None
         }
+    }
+}
+
+// Dirección del contrato y cadena de la petición; el error lleva el mensaje
+// que se devuelve al cliente.
+fn parse_target<'a>(
+    contract_address: &str,
+    chain_id: Option<u64>,
+    config: &'a AppConfig,
+) -> Result<(Address, &'a ChainConfig), (&'static str, AppError)> {
+    if contract_address.trim().is_empty() {
+        return Err((
+            "Dirección de contrato inválida",
+            AppError::InvalidAddress(
+                "falta `contract_address` (o `abi`/`signature` con el ABI)".to_string(),
+            ),
+        ));
+    }
+    let address = contract_address.parse::<Address>().map_err(|e| {
+        (
+            "Dirección de contrato inválida",
+            AppError::InvalidAddress(e.to_string()),
+        )
+    })?;
+    let chain = config
+        .chain(chain_id)
+        .map_err(|e| ("Cadena no soportada", e))?;
+    Ok((address, chain))
+}
+
+#[utoipa::path(
+    post,
+    path = "/encode",
+    tag = "decode",
+    summary = "Construye la call data de una llamada (inverso de /decode)",
+    description = "`function` es el nombre de la función o, si está sobrecargada, su firma; \
+                   puede omitirse si el ABI tiene una sola función (p. ej. con `signature`). \
+                   `arguments` es un array en el orden de los parámetros o un objeto con sus \
+                   nombres: enteros como número o string decimal/hex, direcciones y bytes en \
+                   hex, arrays y tuplas como arrays. El ABI se resuelve igual que en `/decode`.",
+    request_body = EncodeRequest,
+    responses(
+        (status = 200, description = "Call data construida", body = EncodeResponse),
+        (status = 400, description = "Petición inválida o función sobrecargada sin firma", body = ErrorResponse),
+        (status = 403, description = "`save_abi` sin clave de administración", body = ErrorResponse),
+        (status = 422, description = "Función ausente del ABI o argumentos que no encajan con sus tipos", body = ErrorResponse),
+        (status = "5XX", description = "Explorador no disponible o error interno", body = ErrorResponse),
+    )
+)]
+pub async fn encode_handler(
+    http_req: HttpRequest,
+    req: web::Json<EncodeRequest>,
+    config: web::Data<AppConfig>,
+    decoder: web::Data<Decoder>,
+) -> Result<HttpResponse, AppError> {
+    if req.inline.save_abi {
+        require_admin(&http_req, &config.auth)?;
+    }
+    info!(contract = %req.contract_address, chain_id = ?req.chain_id, "codificación solicitada");
+    let (contract, _abi) = resolve_abi(
+        &req.contract_address,
+        req.chain_id,
+        &req.inline,
+        &config,
+        &decoder,
+    )
+    .await
+    .map_err(|(_, e)| e)?;
+
+    let function = match &req.function {
+        Some(function) => find_function(&contract, function)?,
+        None => match contract.functions().collect::<Vec<_>>().as_slice() {
+            [single] => single,
+            _ => {
+                return Err(AppError::InvalidRequest(
+                    "indica `function`: el ABI tiene varias funciones".to_string(),
+                ))
+            }
+        },
     };
+    let tokens = json_to_tokens(function, &req.arguments)?;
+    let call_data = encode_function_call(function, &tokens)?;
+    info!(function = %function.name, bytes = call_data.len(), "codificación completada");
 
-    let client = Client::new();
+    Ok(HttpResponse::Ok().json(EncodeResponse {
+        status: "success".to_string(),
+        function_name: function.name.clone(),
+        signature: function_signature(function),
+        selector: format!("0x{}", hex::encode(function.short_signature())),
+        call_data: format!("0x{}", hex::encode(call_data)),
+        arguments: tokens.iter().map(token_to_json).collect(),
+    }))
+}
 
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", deepseek_api_key)).unwrap(),
-    );
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-    // Construct the prompt for the LLM using the config
-    let prompt = prompt_config
-        .user_prompt_template
-        .replace("{contract_address}", &req.contract_address)
-        .replace("{function_name}", &function_name)
-        .replace("{arguments}", &format!("{:?}", arguments));
-
-    let body = json!({
-        "model": prompt_config.model_settings.model,
-        "messages": [
-            {"role": "system", "content": prompt_config.system_message},
-            {"role": "user", "content": prompt}
-        ],
-        "stream": prompt_config.model_settings.stream
+#[utoipa::path(
+    get,
+    path = "/history",
+    tag = "history",
+    summary = "Lista paginada del historial de decodificaciones y análisis",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Página del historial", body = HistoryListResponse),
+        (status = 400, description = "Filtros inválidos", body = ErrorResponse),
+    )
+)]
+pub async fn history_list_handler(
+    query: web::Query<HistoryQuery>,
+    history: web::Data<HistoryStore>,
+) -> Result<HttpResponse, AppError> {
+    let (page, per_page) = page_bounds(&query);
+    let query = query.into_inner();
+    let (items, total) = run_blocking(&history, move |history| history.query(&query))
+        .await
+        .map_err(|e| {
+            warn!(code = e.code(), error = %e, "consulta de historial fallida");
+            e
+        })?;
+    Ok(HttpResponse::Ok().json(HistoryListResponse {
+        status: "success".to_string(),
+        items,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/history/{id}",
+    tag = "history",
+    summary = "Registro de historial por id",
+    params(("id" = i64, Path, description = "Id del registro")),
+    responses(
+        (status = 200, description = "Registro de historial", body = HistoryEntry),
+        (status = 404, description = "Registro no encontrado", body = ErrorResponse),
+    )
+)]
+pub async fn history_entry_handler(
+    path: web::Path<i64>,
+    history: web::Data<HistoryStore>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+    match run_blocking(&history, move |history| history.get(id)).await? {
+        Some(entry) => Ok(HttpResponse::Ok().json(entry)),
+        None => Err(AppError::NotFound(format!(
+            "Registro de historial no encontrado: {}",
+            id
+        ))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/abis",
+    tag = "abis",
+    summary = "Lista los ABIs en caché con su procedencia",
+    responses(
+        (status = 200, description = "Entradas de la caché", body = AbiListResponse),
+        (status = 500, description = "Error al leer la caché", body = ErrorResponse),
+    )
+)]
+pub async fn abi_list_handler(decoder: web::Data<Decoder>) -> Result<HttpResponse, AppError> {
+    let mut items: Vec<AbiCacheEntry> = run_blocking(&decoder, |decoder| decoder.cached_abis())
+        .await?
+        .iter()
+        .map(abi_cache_entry)
+        .collect();
+    items.sort_by(|a, b| (a.chain_id, &a.address).cmp(&(b.chain_id, &b.address)));
+    Ok(HttpResponse::Ok().json(AbiListResponse {
+        status: "success".to_string(),
+        items,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/abis/export",
+    tag = "abis",
+    summary = "Exporta la caché de ABIs y la base de firmas como bundle",
+    description = "Un único JSON versionado con manifiesto y hashes, importable con \
+                   `POST /abis/import` o `import-abis` en otra instancia.",
+    responses(
+        (status = 200, description = "Bundle de ABIs", body = Object),
+        (status = 500, description = "Error al leer la caché", body = ErrorResponse),
+    )
+)]
+pub async fn abi_export_handler(decoder: web::Data<Decoder>) -> Result<HttpResponse, AppError> {
+    let bundle = run_blocking(&decoder, export_bundle).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"abi-bundle.json\"",
+        ))
+        .json(bundle))
+}
+
+#[utoipa::path(
+    post,
+    path = "/abis/import",
+    tag = "abis",
+    summary = "Importa un bundle exportado por otra instancia",
+    description = "Se verifican el manifiesto y los hashes antes de escribir nada. Con \
+                   autenticación activada requiere una clave `admin`.",
+    params(AbiImportQuery),
+    request_body(content = Object, description = "Bundle de `GET /abis/export`"),
+    responses(
+        (status = 200, description = "Bundle importado", body = AbiImportResponse),
+        (status = 400, description = "Bundle inválido o modificado", body = ErrorResponse),
+        (status = 403, description = "La clave no es de administración", body = ErrorResponse),
+        (status = 422, description = "Algún ABI del bundle no es válido", body = ErrorResponse),
+    )
+)]
+pub async fn abi_import_handler(
+    req: web::Json<AbiBundle>,
+    query: web::Query<AbiImportQuery>,
+    decoder: web::Data<Decoder>,
+) -> Result<HttpResponse, AppError> {
+    let (bundle, overwrite) = (req.into_inner(), query.overwrite.unwrap_or(false));
+    let report = run_blocking(&decoder, move |decoder| {
+        import_bundle(decoder, bundle, overwrite)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(AbiImportResponse {
+        status: "success".to_string(),
+        imported: report.imported,
+        skipped: report.skipped,
+        signatures: report.signatures,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/abis/{chain_id}/{address}",
+    tag = "abis",
+    summary = "ABI en caché de un contrato",
+    params(
+        ("chain_id" = u64, Path, description = "Cadena del contrato"),
+        ("address" = String, Path, description = "Dirección del contrato"),
+    ),
+    responses(
+        (status = 200, description = "Entrada de la caché", body = AbiDetailResponse),
+        (status = 404, description = "El ABI no está en caché", body = ErrorResponse),
+    )
+)]
+pub async fn abi_get_handler(
+    path: web::Path<(u64, String)>,
+    decoder: web::Data<Decoder>,
+) -> Result<HttpResponse, AppError> {
+    let (chain_id, address) = path.into_inner();
+    let contract_address = parse_address(&address)?;
+    match run_blocking(&decoder, move |decoder| {
+        decoder.cached_abi(chain_id, &contract_address)
+    })
+    .await?
+    {
+        Some(cached) => Ok(HttpResponse::Ok().json(abi_detail(cached))),
+        None => Err(AppError::NotFound(format!(
+            "ABI de {:?} (cadena {}) no está en caché",
+            contract_address, chain_id
+        ))),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/abis/{chain_id}/{address}",
+    tag = "abis",
+    summary = "Sube el ABI de un contrato no verificado o privado",
+    description = "Reemplaza la entrada existente. Acepta un array ABI JSON, un artefacto de \
+                   compilación, un array de firmas legibles o una interfaz de Solidity como \
+                   string. El ABI se valida con ethabi antes de aceptarlo. Con autenticación activada requiere una clave `admin`.",
+    params(
+        ("chain_id" = u64, Path, description = "Cadena del contrato"),
+        ("address" = String, Path, description = "Dirección del contrato"),
+    ),
+    request_body = AbiUploadRequest,
+    responses(
+        (status = 201, description = "ABI guardado", body = AbiDetailResponse),
+        (status = 400, description = "Dirección o cadena inválidas", body = ErrorResponse),
+        (status = 403, description = "La clave no es de administración", body = ErrorResponse),
+        (status = 422, description = "ABI inválido", body = ErrorResponse),
+    )
+)]
+pub async fn abi_upload_handler(
+    path: web::Path<(u64, String)>,
+    req: web::Json<AbiUploadRequest>,
+    config: web::Data<AppConfig>,
+    decoder: web::Data<Decoder>,
+) -> Result<HttpResponse, AppError> {
+    let (chain_id, address) = path.into_inner();
+    let contract_address = parse_address(&address)?;
+    config.chain(Some(chain_id))?;
+    let abi = normalize_abi(req.into_inner().abi)?;
+    let cached = run_blocking(&decoder, move |decoder| {
+        decoder.store_abi(chain_id, &contract_address, abi, SOURCE_UPLOAD)
+    })
+    .await?;
+    Ok(HttpResponse::Created().json(abi_detail(cached)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/abis/{chain_id}/{address}",
+    tag = "abis",
+    summary = "Elimina un ABI de la caché",
+    params(
+        ("chain_id" = u64, Path, description = "Cadena del contrato"),
+        ("address" = String, Path, description = "Dirección del contrato"),
+    ),
+    responses(
+        (status = 204, description = "ABI eliminado"),
+        (status = 403, description = "La clave no es de administración", body = ErrorResponse),
+        (status = 404, description = "El ABI no está en caché", body = ErrorResponse),
+    )
+)]
+pub async fn abi_delete_handler(
+    path: web::Path<(u64, String)>,
+    decoder: web::Data<Decoder>,
+) -> Result<HttpResponse, AppError> {
+    let (chain_id, address) = path.into_inner();
+    let contract_address = parse_address(&address)?;
+    let evicted = run_blocking(&decoder, move |decoder| {
+        decoder.evict_abi(chain_id, &contract_address)
+    })
+    .await?;
+    if evicted {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(AppError::NotFound(format!(
+            "ABI de {:?} (cadena {}) no está en caché",
+            contract_address, chain_id
+        )))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/abis/{chain_id}/{address}/refresh",
+    tag = "abis",
+    summary = "Vuelve a descargar el ABI del explorador",
+    description = "Ignora la caché y reemplaza la entrada con la respuesta del explorador.",
+    params(
+        ("chain_id" = u64, Path, description = "Cadena del contrato"),
+        ("address" = String, Path, description = "Dirección del contrato"),
+    ),
+    responses(
+        (status = 200, description = "ABI actualizado", body = AbiDetailResponse),
+        (status = 403, description = "La clave no es de administración", body = ErrorResponse),
+        (status = "4XX", description = "Dirección o cadena inválidas, o contrato no verificado", body = ErrorResponse),
+        (status = "5XX", description = "Explorador no disponible", body = ErrorResponse),
+    )
+)]
+pub async fn abi_refresh_handler(
+    path: web::Path<(u64, String)>,
+    config: web::Data<AppConfig>,
+    decoder: web::Data<Decoder>,
+) -> Result<HttpResponse, AppError> {
+    let (chain_id, address) = path.into_inner();
+    let contract_address = parse_address(&address)?;
+    config.chain(Some(chain_id))?;
+    let cached = decoder
+        .refresh_abi(chain_id, &contract_address)
+        .instrument(info_span!("abi_fetch", chain_id, contract = ?contract_address))
+        .await?;
+    Ok(HttpResponse::Ok().json(abi_detail(cached)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "operations",
+    summary = "El proceso está vivo",
+    responses((status = 200, description = "Servidor en marcha", body = HealthResponse))
+)]
+pub async fn health_handler() -> HttpResponse {
+    HttpResponse::Ok().json(HealthResponse {
+        status: "ok".to_string(),
+        checks: Vec::new(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "operations",
+    summary = "El servidor puede atender peticiones",
+    description = "Comprueba que la configuración del prompt carga, que el directorio de ABIs \
+                   admite escritura y, si hay API key, que el proveedor LLM responde.",
+    responses(
+        (status = 200, description = "Listo", body = HealthResponse),
+        (status = 503, description = "Alguna comprobación falla", body = HealthResponse),
+    )
+)]
+pub async fn readiness_handler(
+    config: web::Data<AppConfig>,
+    upstreams: web::Data<Upstreams>,
+) -> HttpResponse {
+    let prompt_config = load_prompt_config(config.storage.prompt_config.as_deref()).map(|_| ());
+    let abi_dir = check_writable(&config.storage.abi_dir);
+    // Sin API key el análisis está desactivado, pero el servidor sigue listo para /decode
+    let llm = match llm::check_reachable(&upstreams.llm, &config).await {
+        Err(AppError::LlmNotConfigured(_)) => None,
+        result => Some(result),
+    };
+
+    let mut checks = vec![
+        health_check("prompt_config", prompt_config),
+        health_check("abi_dir", abi_dir),
+    ];
+    checks.push(match llm {
+        Some(result) => health_check("llm", result),
+        None => HealthCheck {
+            name: "llm".to_string(),
+            ok: true,
+            details: Some(format!("{} no configurada", config.llm.api_key_env)),
+        },
     });
 
-    info!(
-        "📤 Enviando solicitud a DeepSeek API - Función: {}",
-        function_name
-    );
+    let ready = checks.iter().all(|check| check.ok);
+    for check in checks.iter().filter(|check| !check.ok) {
+        warn!(check = %check.name, details = ?check.details, "comprobación de disponibilidad fallida");
+    }
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    HttpResponse::build(status).json(HealthResponse {
+        status: if ready { "ready" } else { "not_ready" }.to_string(),
+        checks,
+    })
+}
 
-    let response = client
-        .post(api_url)
-        .headers(headers)
-        .json(&body)
-        .send()
-        .await;
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "operations",
+    summary = "Métricas en formato de texto de Prometheus",
+    responses((status = 200, description = "Métricas", body = String, content_type = "text/plain"))
+)]
+pub async fn metrics_handler(metrics: web::Data<Metrics>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render()?))
+}
 
-    match response {
-        Ok(res) => {
-            let status = res.status();
-            info!("📥 Respuesta de DeepSeek - Status: {}", status);
-            let full_response: Result<Value, _> = res.json().await;
-
-            match full_response {
-                Ok(json_response) => {
-                    let content = json_response["choices"][0]["message"]["content"]
-                        .as_str()
-                        .unwrap_or("");
-
-                    // Log del contenido completo para depuración
-                    info!("📄 Contenido completo de la respuesta LLM: {}", content);
-
-                    let risk_level = content
-                        .lines()
-                        .find(|line| {
-                            line.starts_with(&prompt_config.response_format.risk_level_prefix)
-                        })
-                        .and_then(|line| line.split(":").nth(1))
-                        .map(|s| s.trim().to_string());
-
-                    let explanation = if let Some(start) =
-                        content.find(&prompt_config.response_format.explanation_prefix)
-                    {
-                        let after_prefix =
-                            start + prompt_config.response_format.explanation_prefix.len();
-                        if content[after_prefix..].starts_with(':') {
-                            Some(content[(after_prefix + 1)..].trim().to_string())
-                        } else {
-                            Some(content[after_prefix..].trim().to_string())
-                        }
-                    } else {
-                        None
-                    };
-
-                    if status.is_success() {
-                        info!("✅ Análisis completado exitosamente - Función: {}, Nivel de riesgo: {:?}", function_name, risk_level);
-                        HttpResponse::Ok().json(AnalysisResponse {
-                            status: "success".to_string(),
-                            function_name: Some(function_name),
-                            arguments: Some(arguments),
-                            risk_level,
-                            explanation,
-                            message: Some("Análisis de riesgo completado".to_string()),
-                            details: None,
-                        })
-                    } else {
-                        error!(
-                            "❌ Error en API DeepSeek - Status: {}, Respuesta: {}",
-                            status, json_response
-                        );
-                        HttpResponse::InternalServerError().json(AnalysisResponse {
-                            status: "error".to_string(),
-                            function_name: Some(function_name),
-                            arguments: Some(arguments),
-                            risk_level: None,
-                            explanation: None,
-                            message: Some(format!(
-                                "Error en la API de DeepSeek (HTTP status: {})",
-                                status
-                            )),
-                            details: Some(json_response.to_string()),
-                        })
-                    }
-                }
-                Err(e) => {
-                    error!("❌ Error al parsear JSON de DeepSeek: {}", e);
-                    HttpResponse::InternalServerError().json(AnalysisResponse {
-                        status: "error".to_string(),
-                        function_name: Some(function_name),
-                        arguments: Some(arguments),
-                        risk_level: None,
-                        explanation: None,
-                        message: Some("Error al parsear la respuesta JSON de DeepSeek".to_string()),
-                        details: Some(e.to_string()),
-                    })
-                }
-            }
-        }
-        Err(e) => {
-            error!("❌ Error al llamar a API DeepSeek: {}", e);
-            HttpResponse::InternalServerError().json(AnalysisResponse {
-                status: "error".to_string(),
-                function_name: Some(function_name),
-                arguments: Some(arguments),
-                risk_level: None,
-                explanation: None,
-                message: Some("Error al llamar a la API de DeepSeek".to_string()),
-                details: Some(e.to_string()),
-            })
-        }
+fn health_check(name: &str, result: Result<(), AppError>) -> HealthCheck {
+    HealthCheck {
+        name: name.to_string(),
+        ok: result.is_ok(),
+        details: result.err().map(|e| e.to_string()),
+    }
+}
+
+// Crea el directorio si falta y prueba a escribir y borrar un archivo
+fn check_writable(dir: &Path) -> Result<(), AppError> {
+    let probe = dir.join(".readyz");
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&probe, b"ok"))
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| AppError::Storage(format!("{}: {}", dir.display(), e)))
+}
+
+fn parse_address(address: &str) -> Result<Address, AppError> {
+    address
+        .parse::<Address>()
+        .map_err(|e| AppError::InvalidAddress(format!("{}: {}", address, e)))
+}
+
+fn abi_cache_entry(cached: &CachedAbi) -> AbiCacheEntry {
+    AbiCacheEntry {
+        chain_id: cached.chain_id,
+        address: format!("{:?}", cached.address),
+        source: cached.source.clone(),
+        fetched_at: cached
+            .fetched_at
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+            .map(|time| time.to_rfc3339()),
+        hash: cached.hash(),
+        modified: cached.is_modified(),
+        contract_name: cached.contract_name.clone(),
+        compiler_version: cached.compiler_version.clone(),
+        implementation: cached
+            .implementation
+            .map(|address| format!("{:?}", address)),
+    }
+}
+
+fn abi_detail(cached: CachedAbi) -> AbiDetailResponse {
+    AbiDetailResponse {
+        status: "success".to_string(),
+        entry: abi_cache_entry(&cached),
+        abi: cached.abi,
+    }
+}
+
+// Respuesta de error de /decode con el código estable del AppError
+fn decode_error(message: &str, e: &AppError) -> (StatusCode, DecodeResponse) {
+    (
+        e.status_code(),
+        DecodeResponse {
+            status: "error".to_string(),
+            function_name: None,
+            arguments: None,
+            summary: None,
+            token: None,
+            amounts: None,
+            message: Some(message.to_string()),
+            details: Some(e.to_string()),
+            code: Some(e.code().to_string()),
+            abi: None,
+            candidates: None,
+            findings: None,
+        },
+    )
+}
+
+// Respuesta de error de /analysis; conserva la llamada decodificada si ya se tenía
+fn analysis_error(
+    message: &str,
+    e: &AppError,
+    function_name: Option<String>,
+    arguments: Option<Vec<String>>,
+) -> (StatusCode, AnalysisResponse) {
+    (
+        e.status_code(),
+        AnalysisResponse {
+            status: "error".to_string(),
+            function_name,
+            arguments,
+            risk_level: None,
+            explanation: None,
+            token: None,
+            amounts: None,
+            message: Some(message.to_string()),
+            details: Some(e.to_string()),
+            code: Some(e.code().to_string()),
+        },
+    )
+}
+
+// IP del cliente, precedida del nombre de la API key si la petición está autenticada
+fn caller_of(req: &HttpRequest) -> Option<String> {
+    let addr = req
+        .connection_info()
+        .realip_remote_addr()
+        .map(|addr| addr.to_string());
+    match (req.extensions().get::<ApiKeyName>(), addr) {
+        (Some(ApiKeyName(name)), Some(addr)) => Some(format!("{}@{}", name, addr)),
+        (Some(ApiKeyName(name)), None) => Some(name.clone()),
+        (None, addr) => addr,
+    }
+}
+
+// Operaciones síncronas sobre estado compartido (caché de ABIs en disco,
+// SQLite o Redis; historial SQLite) en el pool de hilos bloqueantes de actix,
+// fuera del hilo del worker
+async fn run_blocking<S, T, F>(data: &web::Data<S>, op: F) -> Result<T, AppError>
+where
+    S: Send + Sync + 'static,
+    F: FnOnce(&S) -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    let data = data.clone();
+    web::block(move || op(&data))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+// Petición rechazada antes de procesarla (`save_abi` sin clave de
+// administración): queda en el historial con el error devuelto
+async fn record_rejected(
+    history: &web::Data<HistoryStore>,
+    mut entry: NewHistoryEntry,
+    started: Instant,
+    e: &AppError,
+) {
+    entry.http_status = e.status_code().as_u16();
+    entry.status = "error".to_string();
+    entry.latency_ms = started.elapsed().as_millis() as u64;
+    entry.message = Some(e.to_string());
+    record_history(history, entry).await;
+}
+
+// Un fallo al persistir el historial no debe afectar a la respuesta
+async fn record_history(history: &web::Data<HistoryStore>, entry: NewHistoryEntry) {
+    match run_blocking(history, move |history| history.record(&entry)).await {
+        Ok(id) => debug!(history_id = id, "petición registrada en historial"),
+        Err(e) => error!(error = %e, "no se pudo guardar en historial"),
     }
 }
@@ -0,0 +1,252 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{
+    params, params_from_iter, types::Value as SqlValue, Connection, OptionalExtension, Row,
+};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+use crate::error::AppError;
+use crate::{HistoryEntry, HistoryQuery};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS history (
+    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at       TEXT    NOT NULL,
+    endpoint         TEXT    NOT NULL,
+    caller           TEXT,
+    contract_address TEXT    NOT NULL,
+    call_data        TEXT    NOT NULL,
+    request          TEXT    NOT NULL,
+    http_status      INTEGER NOT NULL,
+    status           TEXT    NOT NULL,
+    function_name    TEXT,
+    arguments        TEXT,
+    risk_level       TEXT,
+    explanation      TEXT,
+    model            TEXT,
+    prompt_hash      TEXT,
+    latency_ms       INTEGER NOT NULL,
+    message          TEXT,
+    details          TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_history_created_at ON history (created_at);
+CREATE INDEX IF NOT EXISTS idx_history_contract ON history (contract_address);
+";
+
+// Datos de una decodificación/análisis a persistir. Los handlers lo van
+// completando a medida que avanzan por cada etapa.
+#[derive(Default)]
+pub struct NewHistoryEntry {
+    pub endpoint: String,
+    pub caller: Option<String>,
+    pub contract_address: String,
+    pub call_data: String,
+    pub request: Value,
+    pub http_status: u16,
+    pub status: String,
+    pub function_name: Option<String>,
+    pub arguments: Option<Vec<String>>,
+    pub risk_level: Option<String>,
+    pub explanation: Option<String>,
+    pub model: Option<String>,
+    pub prompt_hash: Option<String>,
+    pub latency_ms: u64,
+    pub message: Option<String>,
+    pub details: Option<String>,
+}
+
+// Historial de peticiones respaldado por una base de datos SQLite embebida
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+}
+
+impl HistoryStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AppError> {
+        info!(path = %path.as_ref().display(), "abriendo base de datos de historial");
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, AppError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, AppError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(HistoryStore {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn record(&self, entry: &NewHistoryEntry) -> Result<i64, AppError> {
+        let created_at = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let arguments = entry
+            .arguments
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|_| AppError::Storage("Mutex del historial envenenado".to_string()))?;
+        conn.execute(
+            "INSERT INTO history (
+                created_at, endpoint, caller, contract_address, call_data, request,
+                http_status, status, function_name, arguments, risk_level, explanation,
+                model, prompt_hash, latency_ms, message, details
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                created_at,
+                entry.endpoint,
+                entry.caller,
+                entry.contract_address.to_lowercase(),
+                entry.call_data,
+                entry.request.to_string(),
+                entry.http_status,
+                entry.status,
+                entry.function_name,
+                arguments,
+                entry.risk_level,
+                entry.explanation,
+                entry.model,
+                entry.prompt_hash,
+                entry.latency_ms as i64,
+                entry.message,
+                entry.details,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<HistoryEntry>, AppError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|_| AppError::Storage("Mutex del historial envenenado".to_string()))?;
+        let entry = conn
+            .query_row(
+                "SELECT * FROM history WHERE id = ?1",
+                params![id],
+                row_to_entry,
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    // Devuelve la página solicitada (ordenada de más reciente a más antigua)
+    // junto con el total de registros que cumplen los filtros.
+    pub fn query(&self, query: &HistoryQuery) -> Result<(Vec<HistoryEntry>, u64), AppError> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<SqlValue> = Vec::new();
+
+        if let Some(contract) = &query.contract {
+            conditions.push("contract_address = ?");
+            values.push(SqlValue::Text(contract.to_lowercase()));
+        }
+        if let Some(function) = &query.function {
+            conditions.push("function_name = ?");
+            values.push(SqlValue::Text(function.clone()));
+        }
+        if let Some(risk_level) = &query.risk_level {
+            conditions.push("risk_level = ? COLLATE NOCASE");
+            values.push(SqlValue::Text(risk_level.clone()));
+        }
+        if let Some(from) = &query.from {
+            conditions.push("created_at >= ?");
+            values.push(SqlValue::Text(normalize_timestamp(from)?));
+        }
+        if let Some(to) = &query.to {
+            conditions.push("created_at <= ?");
+            values.push(SqlValue::Text(normalize_timestamp(to)?));
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let (page, per_page) = page_bounds(query);
+        let offset = (page as i64 - 1) * per_page as i64;
+
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|_| AppError::Storage("Mutex del historial envenenado".to_string()))?;
+
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM history {}", where_clause),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM history {} ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+            where_clause
+        ))?;
+        values.push(SqlValue::Integer(per_page as i64));
+        values.push(SqlValue::Integer(offset));
+        let entries = stmt
+            .query_map(params_from_iter(values.iter()), row_to_entry)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((entries, total as u64))
+    }
+}
+
+// Página (1-indexada) y tamaño de página efectivos para una consulta
+pub fn page_bounds(query: &HistoryQuery) -> (u32, u32) {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query
+        .per_page
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    (page, per_page)
+}
+
+// Convierte una fecha RFC 3339 al mismo formato UTC con el que se almacena,
+// de modo que la comparación lexicográfica en SQLite sea correcta.
+fn normalize_timestamp(value: &str) -> Result<String, AppError> {
+    let parsed = DateTime::parse_from_rfc3339(value).map_err(|e| {
+        AppError::InvalidRequest(format!(
+            "Fecha inválida '{}' (se espera RFC 3339): {}",
+            value, e
+        ))
+    })?;
+    Ok(parsed
+        .with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+fn row_to_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    let request: String = row.get("request")?;
+    let arguments: Option<String> = row.get("arguments")?;
+    let latency_ms: i64 = row.get("latency_ms")?;
+
+    Ok(HistoryEntry {
+        id: row.get("id")?,
+        created_at: row.get("created_at")?,
+        endpoint: row.get("endpoint")?,
+        caller: row.get("caller")?,
+        contract_address: row.get("contract_address")?,
+        call_data: row.get("call_data")?,
+        request: serde_json::from_str(&request).unwrap_or(Value::Null),
+        http_status: row.get("http_status")?,
+        status: row.get("status")?,
+        function_name: row.get("function_name")?,
+        arguments: arguments.and_then(|a| serde_json::from_str(&a).ok()),
+        risk_level: row.get("risk_level")?,
+        explanation: row.get("explanation")?,
+        model: row.get("model")?,
+        prompt_hash: row.get("prompt_hash")?,
+        latency_ms: latency_ms.max(0) as u64,
+        message: row.get("message")?,
+        details: row.get("details")?,
+    })
+}
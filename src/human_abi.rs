@@ -0,0 +1,787 @@
+use ethabi::{Contract, ParamType};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::error::AppError;
+use crate::InlineAbi;
+
+// Máxima anidación de structs al resolver tipos (evita ciclos entre structs)
+const MAX_STRUCT_DEPTH: usize = 32;
+
+// Declaraciones que empiezan un miembro nuevo; terminan los atributos del anterior
+const MEMBER_KEYWORDS: &[&str] = &[
+    "function",
+    "event",
+    "error",
+    "constructor",
+    "fallback",
+    "receive",
+    "struct",
+    "enum",
+    "modifier",
+    "interface",
+    "contract",
+    "library",
+    "abstract",
+    "pragma",
+    "import",
+    "using",
+];
+
+// Convierte cualquier forma de ABI aceptada a un ABI JSON que ethabi puede
+// cargar:
+// - array ABI JSON o artefacto de compilación con campo "abi"
+// - array de firmas legibles (`["function transfer(address to, uint256 amount) returns (bool)"]`)
+// - texto con firmas legibles, una por línea, o con bloques `interface`/`contract` de Solidity
+// - cualquiera de los anteriores serializado como string JSON
+pub fn normalize_abi(input: Value) -> Result<Value, AppError> {
+    match input {
+        Value::String(text) => parse_abi_str(&text),
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_string) => {
+            let lines: Vec<&str> = items.iter().filter_map(Value::as_str).collect();
+            parse_human_abi(&lines.join("\n"))
+        }
+        Value::Object(mut object) if object.contains_key("abi") => {
+            normalize_abi(object.remove("abi").unwrap_or_default())
+        }
+        other => Ok(other),
+    }
+}
+
+impl InlineAbi {
+    // ABI de la petición en formato JSON; None si no trae `abi` ni `signature`
+    pub fn parse(&self) -> Result<Option<Value>, AppError> {
+        match (&self.abi, &self.signature) {
+            (Some(_), Some(_)) => Err(AppError::InvalidRequest(
+                "indica `abi` o `signature`, no ambos".to_string(),
+            )),
+            (Some(abi), None) => normalize_abi(abi.clone()).map(Some),
+            (None, Some(signature)) => {
+                let abi = parse_human_abi(signature)?;
+                let has_function = abi
+                    .as_array()
+                    .is_some_and(|items| items.iter().any(|item| item["type"] == "function"));
+                if !has_function {
+                    return Err(AppError::InvalidAbi(format!(
+                        "'{}' no es la firma de una función",
+                        signature
+                    )));
+                }
+                Ok(Some(abi))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+// ethabi carga tipos con anchos que Solidity no admite (`uint512`, `int0`,
+// `bytes33`) y que después fallan al codificar o decodificar. Los ABIs JSON
+// pasan por aquí para tener la misma validación que las firmas legibles.
+pub fn check_param_types(contract: &Contract) -> Result<(), AppError> {
+    let functions = contract
+        .functions()
+        .flat_map(|function| function.inputs.iter().chain(&function.outputs))
+        .chain(contract.constructor.iter().flat_map(|c| &c.inputs))
+        .chain(contract.errors().flat_map(|error| &error.inputs))
+        .map(|param| &param.kind);
+    let events = contract
+        .events()
+        .flat_map(|event| &event.inputs)
+        .map(|param| &param.kind);
+    functions.chain(events).try_for_each(check_param_type)
+}
+
+pub fn check_param_type(kind: &ParamType) -> Result<(), AppError> {
+    match kind {
+        ParamType::Int(bits) | ParamType::Uint(bits) if !valid_int_width(*bits) => {
+            Err(AppError::InvalidAbi(format!("tipo desconocido '{}'", kind)))
+        }
+        ParamType::FixedBytes(len) if !valid_fixed_bytes(*len) => {
+            Err(AppError::InvalidAbi(format!("tipo desconocido '{}'", kind)))
+        }
+        ParamType::Array(inner) | ParamType::FixedArray(inner, _) => check_param_type(inner),
+        ParamType::Tuple(items) => items.iter().try_for_each(check_param_type),
+        _ => Ok(()),
+    }
+}
+
+// Igual que `normalize_abi` para el contenido de un archivo o un parámetro de texto
+pub fn parse_abi_str(text: &str) -> Result<Value, AppError> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(json) if json.is_array() || json.is_object() => normalize_abi(json),
+        _ => parse_human_abi(text),
+    }
+}
+
+// Firmas legibles (formato de ethers) y declaraciones de Solidity. Los
+// structs se convierten en tuplas, los enums en uint8 y los tipos de
+// contrato o interfaz declarados en el mismo texto en address.
+pub fn parse_human_abi(source: &str) -> Result<Value, AppError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        items: Vec::new(),
+        structs: HashMap::new(),
+        enums: HashSet::new(),
+        contracts: HashSet::new(),
+    };
+    parser.parse()?;
+    if parser.items.is_empty() {
+        return Err(AppError::InvalidAbi(
+            "el texto no declara funciones, eventos ni errores".to_string(),
+        ));
+    }
+    parser.to_json()
+}
+
+#[derive(Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Number(String),
+    Str,
+    Punct(char),
+}
+
+struct Token {
+    tok: Tok,
+    line: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, AppError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' || c == '\'' {
+            // Solo aparecen en pragma/import, que se ignoran
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token {
+                tok: Tok::Str,
+                line,
+            });
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '$' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let tok = if c.is_ascii_digit() {
+                Tok::Number(word)
+            } else {
+                Tok::Ident(word)
+            };
+            tokens.push(Token { tok, line });
+        } else if c.is_ascii_punctuation() {
+            tokens.push(Token {
+                tok: Tok::Punct(c),
+                line,
+            });
+            i += 1;
+        } else {
+            return Err(AppError::InvalidAbi(format!(
+                "línea {}: carácter inesperado '{}'",
+                line, c
+            )));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone)]
+enum BaseType {
+    Named(String),
+    Tuple(Vec<RawParam>),
+}
+
+#[derive(Clone)]
+struct RawParam {
+    base: BaseType,
+    dims: Vec<String>, // Sufijos de array: "" para dinámico, "3" para [3]
+    name: String,
+    indexed: bool,
+}
+
+enum Item {
+    Function {
+        name: String,
+        inputs: Vec<RawParam>,
+        outputs: Vec<RawParam>,
+        mutability: String,
+    },
+    Event {
+        name: String,
+        inputs: Vec<RawParam>,
+        anonymous: bool,
+    },
+    Error {
+        name: String,
+        inputs: Vec<RawParam>,
+    },
+    Constructor {
+        inputs: Vec<RawParam>,
+        mutability: String,
+    },
+    Fallback {
+        mutability: String,
+    },
+    Receive,
+}
+
+// Atributos tras la lista de parámetros de una función
+struct Attributes {
+    mutability: String,
+    outputs: Vec<RawParam>,
+    external: bool, // false para internal/private, que no forman parte del ABI
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    items: Vec<Item>,
+    structs: HashMap<String, Vec<RawParam>>,
+    enums: HashSet<String>,
+    contracts: HashSet<String>,
+}
+
+impl Parser {
+    fn parse(&mut self) -> Result<(), AppError> {
+        while let Some(tok) = self.peek() {
+            match tok {
+                Tok::Punct(';') => self.pos += 1,
+                Tok::Ident(word) => match word.as_str() {
+                    "pragma" | "import" | "using" => self.skip_statement(),
+                    "interface" | "contract" | "library" => self.parse_container()?,
+                    "abstract" => {
+                        self.pos += 1;
+                        self.parse_container()?;
+                    }
+                    _ => self.parse_member(false)?,
+                },
+                _ => return Err(self.unexpected()),
+            }
+        }
+        Ok(())
+    }
+
+    // `interface Nombre is Base { ... }`; el cuerpo admite lo que no forma
+    // parte del ABI (variables, modificadores, cuerpos de función) y lo ignora
+    fn parse_container(&mut self) -> Result<(), AppError> {
+        self.pos += 1;
+        let name = self.ident()?;
+        self.contracts.insert(name);
+        while !matches!(self.peek(), Some(Tok::Punct('{')) | None) {
+            self.pos += 1;
+        }
+        self.expect('{')?;
+        loop {
+            match self.peek() {
+                Some(Tok::Punct('}')) => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(_) => self.parse_member(true)?,
+                None => return Err(self.error("falta '}' al final del bloque")),
+            }
+        }
+    }
+
+    fn parse_member(&mut self, in_contract: bool) -> Result<(), AppError> {
+        let word = match self.peek() {
+            Some(Tok::Ident(word)) => word,
+            Some(Tok::Punct(';')) => {
+                self.pos += 1;
+                return Ok(());
+            }
+            _ if in_contract => {
+                self.skip_statement();
+                return Ok(());
+            }
+            _ => return Err(self.unexpected()),
+        };
+        match word.as_str() {
+            "function" => {
+                self.pos += 1;
+                self.parse_function(in_contract)
+            }
+            "event" => self.parse_event(),
+            "error" => self.parse_error(),
+            "constructor" => {
+                self.pos += 1;
+                let inputs = self.parse_params()?;
+                let attributes = self.parse_attributes(in_contract)?;
+                self.items.push(Item::Constructor {
+                    inputs,
+                    mutability: attributes.mutability,
+                });
+                Ok(())
+            }
+            "fallback" | "receive" => {
+                self.pos += 1;
+                if self.peek() == Some(Tok::Punct('(')) {
+                    self.parse_params()?;
+                }
+                let attributes = self.parse_attributes(in_contract)?;
+                self.items.push(if word == "receive" {
+                    Item::Receive
+                } else {
+                    Item::Fallback {
+                        mutability: attributes.mutability,
+                    }
+                });
+                Ok(())
+            }
+            "struct" => self.parse_struct(),
+            "enum" => {
+                self.pos += 1;
+                let name = self.ident()?;
+                self.enums.insert(name);
+                self.skip_statement();
+                Ok(())
+            }
+            // Firma sin palabra clave: `transfer(address,uint256)`
+            _ if !in_contract && self.peek_at(1) == Some(Tok::Punct('(')) => {
+                self.parse_function(in_contract)
+            }
+            _ if in_contract => {
+                self.skip_statement();
+                Ok(())
+            }
+            _ => Err(self.error(&format!("declaración no reconocida '{}'", word))),
+        }
+    }
+
+    fn parse_function(&mut self, in_contract: bool) -> Result<(), AppError> {
+        let name = self.ident()?;
+        let inputs = self.parse_params()?;
+        let attributes = self.parse_attributes(in_contract)?;
+        if attributes.external {
+            self.items.push(Item::Function {
+                name,
+                inputs,
+                outputs: attributes.outputs,
+                mutability: attributes.mutability,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_event(&mut self) -> Result<(), AppError> {
+        self.pos += 1;
+        let name = self.ident()?;
+        let inputs = self.parse_params()?;
+        let anonymous = self.peek() == Some(Tok::Ident("anonymous".to_string()));
+        if anonymous {
+            self.pos += 1;
+        }
+        self.items.push(Item::Event {
+            name,
+            inputs,
+            anonymous,
+        });
+        Ok(())
+    }
+
+    fn parse_error(&mut self) -> Result<(), AppError> {
+        self.pos += 1;
+        let name = self.ident()?;
+        let inputs = self.parse_params()?;
+        self.items.push(Item::Error { name, inputs });
+        Ok(())
+    }
+
+    fn parse_struct(&mut self) -> Result<(), AppError> {
+        self.pos += 1;
+        let name = self.ident()?;
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        while self.peek() != Some(Tok::Punct('}')) {
+            let mut field = self.parse_param()?;
+            if field.name.is_empty() {
+                return Err(self.error(&format!("campo sin nombre en el struct {}", name)));
+            }
+            field.indexed = false;
+            fields.push(field);
+            self.expect(';')?;
+        }
+        self.pos += 1;
+        self.structs.insert(name, fields);
+        Ok(())
+    }
+
+    // Visibilidad, mutabilidad y `returns (...)`. Dentro de un contrato se
+    // ignoran también modificadores (`onlyOwner`, `override(A, B)`) y el cuerpo.
+    fn parse_attributes(&mut self, in_contract: bool) -> Result<Attributes, AppError> {
+        let mut attributes = Attributes {
+            mutability: "nonpayable".to_string(),
+            outputs: Vec::new(),
+            external: true,
+        };
+        loop {
+            match self.peek() {
+                Some(Tok::Ident(word)) if !MEMBER_KEYWORDS.contains(&word.as_str()) => {
+                    match word.as_str() {
+                        "returns" => {
+                            self.pos += 1;
+                            attributes.outputs = self.parse_params()?;
+                            continue;
+                        }
+                        "view" | "pure" | "payable" | "nonpayable" => {
+                            attributes.mutability = word.clone()
+                        }
+                        "constant" => attributes.mutability = "view".to_string(),
+                        "internal" | "private" => attributes.external = false,
+                        "external" | "public" | "virtual" | "override" => {}
+                        _ if in_contract => {}
+                        _ => break,
+                    }
+                    self.pos += 1;
+                    if self.peek() == Some(Tok::Punct('(')) {
+                        self.skip_group('(', ')');
+                    }
+                }
+                Some(Tok::Punct(';')) => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(Tok::Punct('{')) => {
+                    self.skip_group('{', '}');
+                    break;
+                }
+                _ => break,
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn parse_params(&mut self) -> Result<Vec<RawParam>, AppError> {
+        self.expect('(')?;
+        let mut params = Vec::new();
+        if self.peek() == Some(Tok::Punct(')')) {
+            self.pos += 1;
+            return Ok(params);
+        }
+        loop {
+            params.push(self.parse_param()?);
+            match self.next() {
+                Some(Tok::Punct(',')) => {}
+                Some(Tok::Punct(')')) => return Ok(params),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.unexpected());
+                }
+            }
+        }
+    }
+
+    // `tipo [indexed] [memory|calldata|storage] [nombre]`
+    fn parse_param(&mut self) -> Result<RawParam, AppError> {
+        let base = match self.peek() {
+            Some(Tok::Punct('(')) => BaseType::Tuple(self.parse_params()?),
+            Some(Tok::Ident(word))
+                if word == "tuple" && self.peek_at(1) == Some(Tok::Punct('(')) =>
+            {
+                self.pos += 1;
+                BaseType::Tuple(self.parse_params()?)
+            }
+            Some(Tok::Ident(word)) if word == "mapping" => {
+                return Err(self.error("los mappings no pueden formar parte del ABI"));
+            }
+            Some(Tok::Ident(_)) => {
+                // Nombres calificados (`IPool.Order`): cuenta el último segmento
+                let mut name = self.ident()?;
+                while self.peek() == Some(Tok::Punct('.')) {
+                    self.pos += 1;
+                    name = self.ident()?;
+                }
+                BaseType::Named(name)
+            }
+            _ => return Err(self.unexpected()),
+        };
+        let mut dims = Vec::new();
+        while self.peek() == Some(Tok::Punct('[')) {
+            self.pos += 1;
+            match self.next() {
+                Some(Tok::Punct(']')) => dims.push(String::new()),
+                Some(Tok::Number(size)) => {
+                    dims.push(size);
+                    self.expect(']')?;
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.unexpected());
+                }
+            }
+        }
+        let mut param = RawParam {
+            base,
+            dims,
+            name: String::new(),
+            indexed: false,
+        };
+        while let Some(Tok::Ident(word)) = self.peek() {
+            match word.as_str() {
+                "indexed" => param.indexed = true,
+                "memory" | "calldata" | "storage" | "payable" => {}
+                _ if param.name.is_empty() => param.name = word.clone(),
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        Ok(param)
+    }
+
+    fn to_json(&self) -> Result<Value, AppError> {
+        let mut abi = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            abi.push(match item {
+                Item::Function {
+                    name,
+                    inputs,
+                    outputs,
+                    mutability,
+                } => json!({
+                    "type": "function",
+                    "name": name,
+                    "inputs": self.params_json(inputs, false)?,
+                    "outputs": self.params_json(outputs, false)?,
+                    "stateMutability": mutability,
+                }),
+                Item::Event {
+                    name,
+                    inputs,
+                    anonymous,
+                } => json!({
+                    "type": "event",
+                    "name": name,
+                    "inputs": self.params_json(inputs, true)?,
+                    "anonymous": anonymous,
+                }),
+                Item::Error { name, inputs } => json!({
+                    "type": "error",
+                    "name": name,
+                    "inputs": self.params_json(inputs, false)?,
+                }),
+                Item::Constructor { inputs, mutability } => json!({
+                    "type": "constructor",
+                    "inputs": self.params_json(inputs, false)?,
+                    "stateMutability": mutability,
+                }),
+                Item::Fallback { mutability } => json!({
+                    "type": "fallback",
+                    "stateMutability": mutability,
+                }),
+                Item::Receive => json!({
+                    "type": "receive",
+                    "stateMutability": "payable",
+                }),
+            });
+        }
+        Ok(Value::Array(abi))
+    }
+
+    fn params_json(&self, params: &[RawParam], event: bool) -> Result<Vec<Value>, AppError> {
+        params
+            .iter()
+            .map(|param| self.param_json(param, event, 0))
+            .collect()
+    }
+
+    fn param_json(&self, param: &RawParam, event: bool, depth: usize) -> Result<Value, AppError> {
+        if depth > MAX_STRUCT_DEPTH {
+            return Err(AppError::InvalidAbi(
+                "structs anidados demasiado profundos o recursivos".to_string(),
+            ));
+        }
+        let mut object = Map::new();
+        object.insert("name".to_string(), json!(param.name));
+        let base = match &param.base {
+            BaseType::Tuple(components) => {
+                object.insert(
+                    "components".to_string(),
+                    self.components_json(components, depth)?,
+                );
+                "tuple".to_string()
+            }
+            BaseType::Named(name) => match self.structs.get(name) {
+                Some(fields) => {
+                    object.insert(
+                        "components".to_string(),
+                        self.components_json(fields, depth)?,
+                    );
+                    object.insert(
+                        "internalType".to_string(),
+                        json!(format!("struct {}", name)),
+                    );
+                    "tuple".to_string()
+                }
+                None if self.enums.contains(name) => "uint8".to_string(),
+                None if self.contracts.contains(name) => "address".to_string(),
+                None => elementary_type(name)?,
+            },
+        };
+        let dims: String = param.dims.iter().map(|dim| format!("[{}]", dim)).collect();
+        object.insert("type".to_string(), json!(format!("{}{}", base, dims)));
+        if event {
+            object.insert("indexed".to_string(), json!(param.indexed));
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn components_json(&self, components: &[RawParam], depth: usize) -> Result<Value, AppError> {
+        components
+            .iter()
+            .map(|component| self.param_json(component, false, depth + 1))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array)
+    }
+
+    fn peek(&self) -> Option<Tok> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<Tok> {
+        self.tokens
+            .get(self.pos + offset)
+            .map(|token| token.tok.clone())
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let tok = self.peek();
+        self.pos += 1;
+        tok
+    }
+
+    fn ident(&mut self) -> Result<String, AppError> {
+        match self.peek() {
+            Some(Tok::Ident(word)) => {
+                self.pos += 1;
+                Ok(word)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), AppError> {
+        if self.peek() == Some(Tok::Punct(punct)) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("se esperaba '{}'", punct)))
+        }
+    }
+
+    // Salta hasta `;` (incluido) o un bloque `{ ... }` completo, sin pasar
+    // del `}` que cierra el contrato
+    fn skip_statement(&mut self) {
+        while let Some(tok) = self.peek() {
+            match tok {
+                Tok::Punct(';') => {
+                    self.pos += 1;
+                    return;
+                }
+                Tok::Punct('{') => {
+                    self.skip_group('{', '}');
+                    return;
+                }
+                Tok::Punct('}') => return,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_group(&mut self, open: char, close: char) {
+        let mut depth = 0;
+        while let Some(tok) = self.next() {
+            if tok == Tok::Punct(open) {
+                depth += 1;
+            } else if tok == Tok::Punct(close) {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn unexpected(&self) -> AppError {
+        let found = match self.peek() {
+            Some(Tok::Ident(word)) | Some(Tok::Number(word)) => format!("'{}'", word),
+            Some(Tok::Punct(c)) => format!("'{}'", c),
+            Some(Tok::Str) => "una cadena".to_string(),
+            None => "el final del texto".to_string(),
+        };
+        self.error(&format!("no se esperaba {}", found))
+    }
+
+    fn error(&self, message: &str) -> AppError {
+        let line = self
+            .tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |token| token.line);
+        AppError::InvalidAbi(format!("línea {}: {}", line, message))
+    }
+}
+
+// Tipos elementales con los alias de Solidity (`uint`, `int`, `byte`). No se
+// usa `ethabi::param_type::Reader`: acepta cualquier nombre como enum (uint8)
+// y una errata pasaría por un tipo válido.
+fn elementary_type(name: &str) -> Result<String, AppError> {
+    let canonical = match name {
+        "uint" => "uint256",
+        "int" => "int256",
+        "byte" => "bytes1",
+        other => other,
+    };
+    let sized = |prefix: &str, valid: fn(usize) -> bool| {
+        canonical
+            .strip_prefix(prefix)
+            .and_then(|size| size.parse::<usize>().ok())
+            .is_some_and(valid)
+    };
+    let known = matches!(canonical, "address" | "bool" | "string" | "bytes")
+        || sized("uint", valid_int_width)
+        || sized("int", valid_int_width)
+        || sized("bytes", valid_fixed_bytes);
+    if known {
+        Ok(canonical.to_string())
+    } else {
+        Err(AppError::InvalidAbi(format!("tipo desconocido '{}'", name)))
+    }
+}
+
+// intN/uintN: de 8 a 256 bits en múltiplos de 8
+pub fn valid_int_width(bits: usize) -> bool {
+    bits.is_multiple_of(8) && (8..=256).contains(&bits)
+}
+
+// bytesN: de 1 a 32 bytes
+pub fn valid_fixed_bytes(len: usize) -> bool {
+    (1..=32).contains(&len)
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::path::PathBuf;
 
 // Struct para la configuración del prompt
 #[derive(Deserialize)]
@@ -22,45 +23,508 @@ pub struct ModelSettings {
     pub stream: bool,
 }
 
+// Configuración del servidor: archivo TOML más overrides por flags/env de la CLI
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub storage: StorageConfig,
+    pub cache: CacheConfig,
+    pub llm: LlmConfig,
+    pub timeouts: TimeoutConfig,
+    pub retry: RetryConfig,
+    pub auth: AuthConfig,
+    pub default_chain_id: u64,
+    pub chains: Vec<ChainConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub workers: Option<usize>,    // None = un worker por núcleo
+    pub cors_origins: Vec<String>, // "*" permite cualquier origen
+    pub log_format: LogFormat,
+}
+
+// Formato de los logs: JSON (una línea por evento, con los campos del span) o texto
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum LogFormat {
+    #[default]
+    Json,
+    Text,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub abi_dir: PathBuf,
+    pub history_db: PathBuf,
+    pub prompt_config: Option<PathBuf>, // None = configuración de prompt embebida
+    pub abi_ttl_secs: Option<u64>,      // None = los ABIs en caché no caducan
+}
+
+// Dónde se guardan las cachés (ABIs, base de firmas y resultados de análisis).
+// Con "sqlite" o "redis" varias réplicas comparten la misma caché.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub backend: CacheBackend,
+    pub sqlite_path: PathBuf,
+    pub redis_url: String,
+    pub redis_prefix: String,   // Prefijo de todas las claves en Redis
+    pub analysis_ttl_secs: u64, // Validez de un análisis del LLM; 0 = no se cachean
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum CacheBackend {
+    #[default]
+    Fs, // Archivos dentro de storage.abi_dir
+    Sqlite,
+    Redis, // Cualquier servidor compatible con el protocolo de Redis
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmConfig {
+    pub api_url: String,
+    pub api_key_env: String, // Nombre de la variable de entorno con la API key
+    pub model: Option<String>, // Sobrescribe model_settings.model del prompt
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    pub explorer_secs: u64,
+    pub llm_secs: u64,
+    pub client_request_secs: u64, // Tiempo máximo para recibir la petición entrante
+    pub connect_secs: u64,        // Establecer conexión con el explorador, el LLM o el RPC
+    pub rpc_secs: u64,            // Cada eth_call al nodo JSON-RPC de la cadena
+    pub token_metadata_secs: u64, // Plazo total para leer los metadatos de un token
+}
+
+// Reintentos y circuit breaker de las llamadas salientes, por servicio
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub explorer: RetryPolicy,
+    #[serde(default = "RetryPolicy::llm_default")]
+    pub llm: RetryPolicy,
+    pub rpc: RetryPolicy,
+}
+
+// Backoff exponencial ante 429/5xx/límites del explorador; el circuito se
+// abre tras `breaker_threshold` llamadas fallidas seguidas
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    pub max_attempts: u32, // Intentos por llamada, incluido el primero
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub breaker_threshold: u32,
+    pub breaker_cooldown_secs: u64, // Tiempo abierto antes de la llamada de prueba
+}
+
+// Autenticación por API key. Las claves se guardan como hash keccak256
+// (`rust_decompile_test hash-key <clave>`), nunca en claro.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    pub public_paths: Vec<String>, // Rutas accesibles sin API key
+    pub keys: Vec<ApiKeyConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    pub name: String,     // Identifica al cliente en logs e historial
+    pub key_hash: String, // 0x + keccak256 de la clave en hex
+    #[serde(default)]
+    pub decode: RateLimitConfig, // Presupuesto de /decode y demás endpoints
+    #[serde(default = "RateLimitConfig::llm_default")]
+    pub llm: RateLimitConfig, // Presupuesto de los endpoints que llaman al LLM
+    #[serde(default)]
+    pub admin: bool, // Permite modificar la caché de ABIs (PUT/DELETE/refresh en /abis)
+}
+
+// Token bucket (per_minute de recarga, burst de capacidad) más cuota diaria opcional
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub per_minute: u32,
+    pub burst: Option<u32>,       // None = per_minute
+    pub daily_quota: Option<u64>, // Peticiones por día UTC; None = sin cuota
+}
+
+// Entrada del registro de cadenas: explorador compatible con la API de Etherscan
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    pub chain_id: u64,
+    pub name: String,
+    pub explorer_api_url: String,
+    pub api_key_env: Option<String>,
+    pub rpc_url: Option<String>, // Nodo JSON-RPC para eth_call; None = sin metadatos de tokens
+}
+
 // Struct para la petición JSON entrante del endpoint /decode
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct DecodeRequest {
-    pub contract_address: String,
+    #[serde(default)]
+    pub contract_address: String, // Opcional si se indica `abi` o `signature`
     pub call_data: String,
+    pub chain_id: Option<u64>, // None = default_chain_id de la configuración
+    #[serde(flatten)]
+    pub inline: InlineAbi,
+    // Recodifica los argumentos y compara con la call data (ver `findings`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+}
+
+// ABI aportado en la petición de /decode, /analysis o /encode. Evita consultar
+// la caché y el explorador: contratos sin desplegar, cadenas privadas o
+// código en revisión.
+#[derive(Deserialize, Serialize, Default)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct InlineAbi {
+    // Array ABI JSON, artefacto de compilación, firmas legibles o interfaz de Solidity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abi: Option<Value>,
+    // Firma de la función llamada: `transfer(address,uint256)` o
+    // `function transfer(address to, uint256 amount)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    // Guarda `abi` en la caché para `contract_address` (con autenticación, solo claves admin)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub save_abi: bool,
 }
 
 // Struct para la respuesta JSON saliente del endpoint /decode
 #[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct DecodeResponse {
     pub status: String, // "success" or "error"
     pub function_name: Option<String>,
     pub arguments: Option<Vec<String>>, // Represent arguments as strings for simplicity
+    pub summary: Option<String>, // Resumen en una línea si la firma es de un estándar conocido
+    pub token: Option<TokenMetadata>, // Metadatos del contrato llamado, si se leyeron
+    pub amounts: Option<Vec<TokenAmount>>, // Argumentos que son importes del token
     pub message: Option<String>,
     pub details: Option<String>, // For additional error info
+    pub code: Option<String>,    // Stable machine-readable error code
     pub abi: Option<Value>,      // Include ABI in successful response for analysis endpoint
+    pub candidates: Option<Vec<CallCandidate>>, // Solo si el selector es ambiguo o nada encaja
+    pub findings: Option<Vec<EncodingFinding>>, // Solo en modo estricto; vacío si es canónica
+}
+
+// Diferencia entre la call data recibida y la codificación canónica de los
+// argumentos decodificados (modo estricto)
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct EncodingFinding {
+    pub kind: FindingKind,
+    pub path: Option<String>, // Argumento afectado, p. ej. `orders[1].0`
+    pub offset: usize,        // Posición en bytes dentro de call_data, selector incluido
+    pub detail: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    DirtyPadding,      // Bits no nulos en el relleno de address, uintN, bytesN, bool o bytes
+    NonCanonicalValue, // Valor fuera de rango que ethabi acepta (p. ej. bool distinto de 0/1)
+    NonCanonicalOffset, // Offset de un argumento dinámico distinto del canónico
+    NonCanonicalLength, // Longitud de bytes/string/array distinta de la canónica
+    ExtraBytes,        // Datos sobrantes tras la codificación canónica
+    MissingBytes,      // Call data más corta que la codificación canónica
+}
+
+// Metadatos de un token leídos del contrato con eth_call. Cada campo es None
+// si el contrato no implementa la función o revierte.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct TokenMetadata {
+    pub standard: Option<TokenStandard>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+}
+
+// ERC-20 si responde a decimals(); ERC-721 y ERC-1155 según supportsInterface
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
+// Argumento uint256 que es un importe del token llamado, en sus unidades
+#[derive(Serialize, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct TokenAmount {
+    pub index: usize,
+    pub argument: String,  // Nombre del parámetro, o `#<índice>` si no tiene
+    pub raw: String,       // Unidades base
+    pub formatted: String, // `1.5 USDC`
+}
+
+// Función del ABI con el selector de la llamada. Se informan todas cuando
+// varias firmas comparten selector o cuando ninguna decodifica los argumentos.
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct CallCandidate {
+    pub function_name: String,
+    pub signature: String,
+    pub decoded: bool, // Los argumentos encajan con esta firma
+    pub arguments: Option<Vec<String>>,
+    pub error: Option<String>, // Motivo por el que no encajan
 }
 
 // Struct para la petición JSON entrante del endpoint /analysis
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct AnalysisRequest {
-    pub contract_address: String,
+    #[serde(default)]
+    pub contract_address: String, // Opcional si se indica `abi` o `signature`
     pub call_data: String,
+    pub chain_id: Option<u64>, // None = default_chain_id de la configuración
+    #[serde(flatten)]
+    pub inline: InlineAbi,
 }
 
 // Struct para la respuesta JSON saliente del endpoint /analysis
 #[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct AnalysisResponse {
-    pub status: String,                 // "success" or "error"
-    pub function_name: Option<String>,  // Include decoded function name
-    pub arguments: Option<Vec<String>>, // Include decoded arguments
-    pub risk_level: Option<String>,     // e.g., "Low", "Medium", "High", "Caution", "Unknown"
-    pub explanation: Option<String>,    // Explanation from the LLM
+    pub status: String,                    // "success" or "error"
+    pub function_name: Option<String>,     // Include decoded function name
+    pub arguments: Option<Vec<String>>,    // Include decoded arguments
+    pub risk_level: Option<String>,        // e.g., "Low", "Medium", "High", "Caution", "Unknown"
+    pub explanation: Option<String>,       // Explanation from the LLM
+    pub token: Option<TokenMetadata>,      // Metadatos del contrato llamado, si se leyeron
+    pub amounts: Option<Vec<TokenAmount>>, // Argumentos que son importes del token
     pub message: Option<String>,
     pub details: Option<String>, // For additional error info
+    pub code: Option<String>,    // Stable machine-readable error code
+}
+
+// Struct para la petición JSON entrante del endpoint /encode
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct EncodeRequest {
+    #[serde(default)]
+    pub contract_address: String, // Opcional si se indica `abi` o `signature`
+    pub chain_id: Option<u64>,
+    #[serde(flatten)]
+    pub inline: InlineAbi,
+    // Nombre o firma de la función; opcional si `signature` ya la identifica
+    pub function: Option<String>,
+    // Array en el orden de los parámetros u objeto con sus nombres
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+// Struct para la respuesta JSON saliente del endpoint /encode
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct EncodeResponse {
+    pub status: String, // always "success"; los errores usan ErrorResponse
+    pub function_name: String,
+    pub signature: String,
+    pub selector: String,
+    pub call_data: String,
+    pub arguments: Vec<Value>, // Argumentos codificados; enteros y direcciones como strings
+}
+
+// Cuerpo JSON genérico para errores de endpoints sin respuesta propia
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    pub status: String, // always "error"
+    pub code: String,   // Stable machine-readable error code, e.g. "INVALID_ADDRESS"
+    pub message: String,
+    pub details: Option<String>,
+}
+
+// Struct para la respuesta JSON de GET /healthz y GET /readyz
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct HealthResponse {
+    pub status: String, // "ok", "ready" or "not_ready"
+    pub checks: Vec<HealthCheck>,
+}
+
+// Resultado de una comprobación de /readyz
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct HealthCheck {
+    pub name: String, // "prompt_config", "abi_dir" or "llm"
+    pub ok: bool,
+    pub details: Option<String>,
+}
+
+// Parámetros de consulta del endpoint POST /abis/import
+#[derive(Deserialize)]
+#[cfg_attr(
+    feature = "server",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+pub struct AbiImportQuery {
+    pub overwrite: Option<bool>, // Reemplaza las entradas ya presentes (por defecto no)
+}
+
+// Struct para la respuesta JSON saliente del endpoint POST /abis/import
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AbiImportResponse {
+    pub status: String,
+    pub imported: usize,
+    pub skipped: usize,    // Ya estaban en caché
+    pub signatures: usize, // Selectores de la base de firmas importados
+}
+
+// Parámetros de consulta del endpoint GET /history
+#[derive(Deserialize, Default)]
+#[cfg_attr(
+    feature = "server",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+pub struct HistoryQuery {
+    pub contract: Option<String>,
+    pub function: Option<String>,
+    pub risk_level: Option<String>,
+    pub from: Option<String>, // RFC 3339, inclusive
+    pub to: Option<String>,   // RFC 3339, inclusive
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
 }
 
-// Declaraciones de módulos
+// Registro persistido de una decodificación o análisis
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub created_at: String,
+    pub endpoint: String, // "decode" or "analysis"
+    pub caller: Option<String>,
+    pub contract_address: String,
+    pub call_data: String,
+    pub request: Value,
+    pub http_status: u16,
+    pub status: String,
+    pub function_name: Option<String>,
+    pub arguments: Option<Vec<String>>,
+    pub risk_level: Option<String>,
+    pub explanation: Option<String>,
+    pub model: Option<String>,
+    pub prompt_hash: Option<String>,
+    pub latency_ms: u64,
+    pub message: Option<String>,
+    pub details: Option<String>,
+}
+
+// Struct para la respuesta JSON saliente del endpoint GET /history
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct HistoryListResponse {
+    pub status: String,
+    pub items: Vec<HistoryEntry>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+}
+
+// Entrada de la caché de ABIs tal como la devuelven los endpoints /abis
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AbiCacheEntry {
+    pub chain_id: Option<u64>, // None en archivos antiguos sin metadatos
+    pub address: String,
+    pub source: String,             // "explorer", "upload" o "legacy"
+    pub fetched_at: Option<String>, // RFC 3339
+    pub hash: String,               // keccak256 del ABI serializado
+    pub modified: bool,             // El ABI no coincide con el hash registrado al guardarlo
+    pub contract_name: Option<String>,
+    pub compiler_version: Option<String>,
+    pub implementation: Option<String>, // Implementación, si el contrato es un proxy
+}
+
+// Struct para la respuesta JSON saliente del endpoint GET /abis
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AbiListResponse {
+    pub status: String,
+    pub items: Vec<AbiCacheEntry>,
+}
+
+// Respuesta de los endpoints /abis/{chain_id}/{address}: metadatos más el ABI
+#[derive(Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AbiDetailResponse {
+    pub status: String,
+    pub entry: AbiCacheEntry,
+    pub abi: Value,
+}
+
+// Struct para la petición JSON de PUT /abis/{chain_id}/{address}. Se acepta
+// también un artefacto de compilación completo (se usa su campo "abi"), un
+// array de firmas legibles o un string con una interfaz de Solidity.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AbiUploadRequest {
+    pub abi: Value,
+}
+
+// Declaraciones de módulos. El núcleo de decodificación no depende de
+// actix-web ni de reqwest; el resto se activa con features de cargo.
 pub mod abi;
+#[cfg(feature = "server")]
+pub mod auth;
+pub mod bundle;
 pub mod config;
 pub mod decode;
+pub mod decoder;
+pub mod disasm;
+pub mod encode;
+pub mod error;
+#[cfg(feature = "explorer")]
+pub mod explorer;
+#[cfg(feature = "server")]
 pub mod handlers;
+#[cfg(feature = "server")]
+pub mod history;
+pub mod human_abi;
+#[cfg(feature = "llm")]
+pub mod llm;
+#[cfg(feature = "server")]
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod openapi;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(any(feature = "explorer", feature = "llm"))]
+pub mod request_id;
+#[cfg(feature = "explorer")]
+pub mod rpc;
+pub mod store;
+pub mod strict;
+pub mod summary;
+#[cfg(feature = "explorer")]
+pub mod token;
+#[cfg(any(feature = "explorer", feature = "llm"))]
+pub mod upstream;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use decoder::{AbiCache, AbiProvider, Decoder};
@@ -0,0 +1,316 @@
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Method,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use crate::error::AppError;
+use crate::store::{blocking, open_store, CacheStore, NAMESPACE_ANALYSIS};
+use crate::upstream::Upstream;
+use crate::{AppConfig, PromptConfig};
+
+// Petición ya construida para el LLM, con los datos que se guardan en el historial
+pub struct LlmPrompt {
+    pub model: String,
+    pub prompt_hash: String, // keccak256 del mensaje de sistema + prompt de usuario
+    pub body: Value,
+}
+
+// Veredicto extraído de la respuesta del LLM
+pub struct RiskVerdict {
+    pub risk_level: Option<String>,
+    pub explanation: Option<String>,
+    pub prompt_tokens: Option<u64>, // Consumo informado por el proveedor en "usage"
+    pub completion_tokens: Option<u64>,
+}
+
+// Lee la API key del proveedor LLM desde la variable de entorno configurada
+pub fn api_key(config: &AppConfig) -> Result<String, AppError> {
+    let api_key_env = &config.llm.api_key_env;
+    env::var(api_key_env).map_err(|_| {
+        AppError::LlmNotConfigured(format!(
+            "Asegúrate de configurar la variable de entorno {} en tu archivo .env",
+            api_key_env
+        ))
+    })
+}
+
+pub fn build_prompt(
+    config: &AppConfig,
+    prompt_config: &PromptConfig,
+    contract_address: &str,
+    function_name: &str,
+    arguments: &[String],
+    summary: Option<&str>,
+) -> LlmPrompt {
+    // Construct the prompt for the LLM using the config
+    let template = &prompt_config.user_prompt_template;
+    let mut prompt = template
+        .replace("{contract_address}", contract_address)
+        .replace("{function_name}", function_name)
+        .replace("{arguments}", &format!("{:?}", arguments))
+        .replace("{summary}", summary.unwrap_or("no disponible"));
+    // Plantillas propias anteriores a {summary}: el resumen va al final
+    if let (Some(summary), false) = (summary, template.contains("{summary}")) {
+        prompt.push_str(&format!("\n\nResumen de la llamada: {}", summary));
+    }
+
+    let model = config
+        .llm
+        .model
+        .clone()
+        .unwrap_or_else(|| prompt_config.model_settings.model.clone());
+
+    let prompt_hash = format!(
+        "0x{}",
+        hex::encode(Keccak256::digest(format!(
+            "{}\n{}",
+            prompt_config.system_message, prompt
+        )))
+    );
+
+    let body = json!({
+        "model": model,
+        "messages": [
+            {"role": "system", "content": prompt_config.system_message},
+            {"role": "user", "content": prompt}
+        ],
+        "stream": prompt_config.model_settings.stream
+    });
+
+    LlmPrompt {
+        model,
+        prompt_hash,
+        body,
+    }
+}
+
+pub async fn request_verdict(
+    upstream: &Upstream,
+    config: &AppConfig,
+    prompt_config: &PromptConfig,
+    prompt: &LlmPrompt,
+) -> Result<RiskVerdict, AppError> {
+    let llm_api_key = api_key(config)?;
+    let api_url = Url::parse(&config.llm.api_url).map_err(|e| AppError::Internal(e.to_string()))?;
+    let authorization = HeaderValue::from_str(&format!("Bearer {}", llm_api_key)).map_err(|e| {
+        AppError::LlmNotConfigured(format!(
+            "{} contiene caracteres inválidos: {}",
+            config.llm.api_key_env, e
+        ))
+    })?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, authorization);
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    info!(model = %prompt.model, "enviando solicitud al LLM");
+
+    let (api_url, headers) = (&api_url, &headers);
+    let json_response = upstream
+        .execute(|| async move {
+            let res = upstream
+                .request(Method::POST, api_url.clone())
+                .headers(headers.clone())
+                .json(&prompt.body)
+                .send()
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "no se pudo llamar al LLM");
+                    AppError::LlmUnavailable(e.to_string())
+                })?;
+
+            let status = res.status();
+            info!(status = status.as_u16(), "respuesta del LLM recibida");
+            if !status.is_success() {
+                let body = res.text().await.unwrap_or_default();
+                error!(status = status.as_u16(), response = %body, "el LLM devolvió un error");
+                // 429 y 5xx indican que el proveedor no está disponible
+                // temporalmente (se reintentan); el resto es una respuesta inválida.
+                let details = format!("HTTP status {}: {}", status, body);
+                return Err(if status.as_u16() == 429 || status.is_server_error() {
+                    AppError::LlmUnavailable(details)
+                } else {
+                    AppError::LlmBadResponse(details)
+                });
+            }
+            res.json::<Value>().await.map_err(|e| {
+                error!(error = %e, "respuesta del LLM no es JSON");
+                AppError::LlmBadResponse(e.to_string())
+            })
+        })
+        .await?;
+
+    let content = json_response["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or("");
+
+    // Contenido completo solo a nivel debug: puede ser largo
+    debug!(content, "contenido de la respuesta del LLM");
+
+    let mut verdict = parse_verdict(prompt_config, content);
+    verdict.prompt_tokens = json_response["usage"]["prompt_tokens"].as_u64();
+    verdict.completion_tokens = json_response["usage"]["completion_tokens"].as_u64();
+    Ok(verdict)
+}
+
+// Comprueba que el proveedor LLM responde (cualquier status HTTP vale); falla
+// con LlmNotConfigured si no hay API key. Es una sonda: sin reintentos.
+pub async fn check_reachable(upstream: &Upstream, config: &AppConfig) -> Result<(), AppError> {
+    api_key(config)?;
+    upstream
+        .request(Method::HEAD, &config.llm.api_url)
+        .send()
+        .await
+        .map_err(|e| AppError::LlmUnavailable(e.to_string()))?;
+    Ok(())
+}
+
+// Veredictos ya obtenidos, por modelo y hash del prompt (que incluye el
+// contrato, la función y los argumentos): la misma llamada no se vuelve a
+// enviar al LLM mientras no caduque. Una entrada caducada se borra al leerla;
+// el resto, como mucho una vez por TTL al guardar otra (en Redis caducan en el
+// servidor). Los fallos del almacén solo se registran.
+pub struct AnalysisCache {
+    store: Arc<dyn CacheStore>,
+    ttl: Option<Duration>,              // None = desactivada
+    last_purge: Mutex<Option<Instant>>, // Última limpieza de entradas caducadas
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedVerdict {
+    risk_level: Option<String>,
+    explanation: Option<String>,
+    cached_at: u64, // Segundos unix
+}
+
+impl AnalysisCache {
+    pub fn new(store: Box<dyn CacheStore>, ttl: Option<Duration>) -> Self {
+        AnalysisCache {
+            store: Arc::from(store),
+            ttl,
+            last_purge: Mutex::new(None),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Result<Self, AppError> {
+        let ttl = Some(config.cache.analysis_ttl_secs)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+        Ok(Self::new(open_store(config, NAMESPACE_ANALYSIS)?, ttl))
+    }
+
+    pub async fn get(&self, prompt: &LlmPrompt) -> Option<RiskVerdict> {
+        let ttl = self.ttl?;
+        let (store, key) = (self.store.clone(), analysis_key(prompt));
+        let value = match blocking(move || store.get(&key)).await {
+            Ok(value) => value?,
+            Err(e) => {
+                warn!(code = e.code(), error = %e, "no se pudo leer la caché de análisis");
+                return None;
+            }
+        };
+        let cached: CachedVerdict = serde_json::from_str(&value).ok()?;
+        if now_secs().saturating_sub(cached.cached_at) >= ttl.as_secs() {
+            let (store, key) = (self.store.clone(), analysis_key(prompt));
+            if let Err(e) = blocking(move || store.remove(&key)).await {
+                warn!(code = e.code(), error = %e, "no se pudo borrar el análisis caducado");
+            }
+            return None;
+        }
+        debug!(prompt_hash = %prompt.prompt_hash, "análisis servido desde la caché");
+        Some(RiskVerdict {
+            risk_level: cached.risk_level,
+            explanation: cached.explanation,
+            prompt_tokens: None,
+            completion_tokens: None,
+        })
+    }
+
+    pub async fn put(&self, prompt: &LlmPrompt, verdict: &RiskVerdict) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        let cached = CachedVerdict {
+            risk_level: verdict.risk_level.clone(),
+            explanation: verdict.explanation.clone(),
+            cached_at: now_secs(),
+        };
+        let (store, key) = (self.store.clone(), analysis_key(prompt));
+        let result = match serde_json::to_string(&cached) {
+            Ok(value) => blocking(move || store.put_expiring(&key, &value, ttl)).await,
+            Err(e) => Err(AppError::Internal(e.to_string())),
+        };
+        if let Err(e) = result {
+            warn!(code = e.code(), error = %e, "no se pudo guardar el análisis en caché");
+        }
+        if self.purge_due(ttl) {
+            let store = self.store.clone();
+            match blocking(move || store.purge_older_than(ttl)).await {
+                Ok(purged) => debug!(purged, "análisis caducados eliminados de la caché"),
+                Err(e) => {
+                    warn!(code = e.code(), error = %e, "no se pudieron borrar los análisis caducados")
+                }
+            }
+        }
+    }
+
+    // La primera escritura limpia lo que quedó de ejecuciones anteriores
+    fn purge_due(&self, ttl: Duration) -> bool {
+        let Ok(mut last_purge) = self.last_purge.lock() else {
+            return false;
+        };
+        if last_purge.is_some_and(|last| last.elapsed() < ttl) {
+            return false;
+        }
+        *last_purge = Some(Instant::now());
+        true
+    }
+}
+
+fn analysis_key(prompt: &LlmPrompt) -> String {
+    hex::encode(Keccak256::digest(
+        format!("{}\n{}", prompt.model, prompt.prompt_hash).as_bytes(),
+    ))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn parse_verdict(prompt_config: &PromptConfig, content: &str) -> RiskVerdict {
+    let risk_level = content
+        .lines()
+        .find(|line| line.starts_with(&prompt_config.response_format.risk_level_prefix))
+        .and_then(|line| line.split(":").nth(1))
+        .map(|s| s.trim().to_string());
+
+    let explanation =
+        if let Some(start) = content.find(&prompt_config.response_format.explanation_prefix) {
+            let after_prefix = start + prompt_config.response_format.explanation_prefix.len();
+            if content[after_prefix..].starts_with(':') {
+                Some(content[(after_prefix + 1)..].trim().to_string())
+            } else {
+                Some(content[after_prefix..].trim().to_string())
+            }
+        } else {
+            None
+        };
+
+    RiskVerdict {
+        risk_level,
+        explanation,
+        prompt_tokens: None,
+        completion_tokens: None,
+    }
+}
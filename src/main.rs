@@ -1,27 +1,309 @@
-use actix_web::{web, App, HttpServer};
 use actix_cors::Cors;
-use log::info;
+use actix_web::middleware::from_fn;
+use actix_web::{web, App, HttpServer};
+use clap::{Args, Parser};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+use rust_decompile_test::abi::FsAbiCache;
+use rust_decompile_test::auth::{authenticate, Authenticator};
+use rust_decompile_test::config::load_app_config;
+use rust_decompile_test::error::AppError;
+use rust_decompile_test::handlers;
+use rust_decompile_test::history::HistoryStore;
+use rust_decompile_test::llm::AnalysisCache;
+use rust_decompile_test::metrics::{instrumented_decoder, track_requests, Metrics};
+use rust_decompile_test::request_id::{assign_request_id, REQUEST_ID_HEADER};
+use rust_decompile_test::token::TokenResolver;
+use rust_decompile_test::upstream::Upstreams;
+use rust_decompile_test::{AppConfig, CacheBackend, LogFormat};
+
+mod cli;
+
+use cli::{Command, OutputFormat};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Servicio web y CLI de decodificación y análisis de riesgo de transacciones"
+)]
+struct Cli {
+    #[command(flatten)]
+    overrides: ConfigOverrides,
+    /// Formato de salida de los subcomandos
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    output: OutputFormat,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+// Flags de configuración; cada una puede darse también por variable de
+// entorno y tiene prioridad sobre el archivo de configuración.
+#[derive(Args)]
+struct ConfigOverrides {
+    /// Archivo de configuración TOML (por defecto `config.toml` si existe)
+    #[arg(long, global = true, env = "RDT_CONFIG")]
+    config: Option<PathBuf>,
+    /// Dirección de escucha, p. ej. 127.0.0.1:8080
+    #[arg(long, global = true, env = "RDT_BIND")]
+    bind: Option<String>,
+    /// Número de workers de actix
+    #[arg(long, global = true, env = "RDT_WORKERS")]
+    workers: Option<usize>,
+    /// Directorio de caché de ABIs
+    #[arg(long, global = true, env = "RDT_ABI_DIR")]
+    abi_dir: Option<PathBuf>,
+    /// Base de datos SQLite del historial
+    #[arg(long, global = true, env = "RDT_HISTORY_DB")]
+    history_db: Option<PathBuf>,
+    /// Archivo JSON con la configuración del prompt
+    #[arg(long, global = true, env = "RDT_PROMPT_CONFIG")]
+    prompt_config: Option<PathBuf>,
+    /// Segundos tras los que un ABI en caché se revalida con el explorador
+    #[arg(long, global = true, env = "RDT_ABI_TTL_SECS")]
+    abi_ttl_secs: Option<u64>,
+    /// Backend de las cachés (ABIs, firmas y análisis)
+    #[arg(long, global = true, env = "RDT_CACHE_BACKEND", value_enum)]
+    cache_backend: Option<CacheBackend>,
+    /// Base de datos SQLite del backend de caché "sqlite"
+    #[arg(long, global = true, env = "RDT_CACHE_SQLITE_PATH")]
+    cache_sqlite_path: Option<PathBuf>,
+    /// URL del servidor del backend de caché "redis", p. ej. redis://127.0.0.1:6379
+    #[arg(long, global = true, env = "RDT_CACHE_REDIS_URL")]
+    cache_redis_url: Option<String>,
+    /// Cadena usada cuando la petición no indica `chain_id`
+    #[arg(long, global = true, env = "RDT_DEFAULT_CHAIN_ID")]
+    default_chain_id: Option<u64>,
+    /// URL del endpoint de chat completions del proveedor LLM
+    #[arg(long, global = true, env = "RDT_LLM_API_URL")]
+    llm_api_url: Option<String>,
+    /// Variable de entorno que contiene la API key del LLM
+    #[arg(long, global = true, env = "RDT_LLM_API_KEY_ENV")]
+    llm_api_key_env: Option<String>,
+    /// Modelo LLM (sobrescribe el de la configuración del prompt)
+    #[arg(long, global = true, env = "RDT_LLM_MODEL")]
+    llm_model: Option<String>,
+    /// Timeout en segundos para las llamadas al explorador
+    #[arg(long, global = true, env = "RDT_EXPLORER_TIMEOUT_SECS")]
+    explorer_timeout_secs: Option<u64>,
+    /// Timeout en segundos para las llamadas al LLM
+    #[arg(long, global = true, env = "RDT_LLM_TIMEOUT_SECS")]
+    llm_timeout_secs: Option<u64>,
+    /// Formato de los logs
+    #[arg(long, global = true, env = "RDT_LOG_FORMAT", value_enum)]
+    log_format: Option<LogFormat>,
+    /// Exige API key en las rutas no públicas (ver [auth] en la configuración)
+    #[arg(long, global = true, env = "RDT_AUTH_ENABLED")]
+    auth_enabled: Option<bool>,
+    /// Orígenes CORS permitidos ("*" para cualquiera); separados por comas
+    #[arg(long, global = true, env = "RDT_CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Option<Vec<String>>,
+}
 
-use rust_decompile_test::handlers::{analysis_handler, decode_handler};
+impl ConfigOverrides {
+    fn apply(self, config: &mut AppConfig) {
+        if let Some(bind) = self.bind {
+            config.server.bind = bind;
+        }
+        if let Some(workers) = self.workers {
+            config.server.workers = Some(workers);
+        }
+        if let Some(origins) = self.cors_origins {
+            config.server.cors_origins = origins;
+        }
+        if let Some(abi_dir) = self.abi_dir {
+            config.storage.abi_dir = abi_dir;
+        }
+        if let Some(history_db) = self.history_db {
+            config.storage.history_db = history_db;
+        }
+        if let Some(prompt_config) = self.prompt_config {
+            config.storage.prompt_config = Some(prompt_config);
+        }
+        if let Some(secs) = self.abi_ttl_secs {
+            config.storage.abi_ttl_secs = Some(secs);
+        }
+        if let Some(backend) = self.cache_backend {
+            config.cache.backend = backend;
+        }
+        if let Some(path) = self.cache_sqlite_path {
+            config.cache.sqlite_path = path;
+        }
+        if let Some(url) = self.cache_redis_url {
+            config.cache.redis_url = url;
+        }
+        if let Some(chain_id) = self.default_chain_id {
+            config.default_chain_id = chain_id;
+        }
+        if let Some(api_url) = self.llm_api_url {
+            config.llm.api_url = api_url;
+        }
+        if let Some(api_key_env) = self.llm_api_key_env {
+            config.llm.api_key_env = api_key_env;
+        }
+        if let Some(model) = self.llm_model {
+            config.llm.model = Some(model);
+        }
+        if let Some(secs) = self.explorer_timeout_secs {
+            config.timeouts.explorer_secs = secs;
+        }
+        if let Some(secs) = self.llm_timeout_secs {
+            config.timeouts.llm_secs = secs;
+        }
+        if let Some(log_format) = self.log_format {
+            config.server.log_format = log_format;
+        }
+        if let Some(enabled) = self.auth_enabled {
+            config.auth.enabled = enabled;
+        }
+    }
+}
+
+fn build_cors(origins: &[String]) -> Cors {
+    if origins.iter().any(|origin| origin == "*") {
+        return Cors::permissive();
+    }
+    origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
+        .allow_any_header()
+        .expose_headers(vec![REQUEST_ID_HEADER, "retry-after"])
+        .max_age(3600)
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenvy::dotenv().ok();
-    env_logger::init();
 
-    let server_address = "127.0.0.1:8080";
+    let cli = Cli::parse();
+    let config = match load_and_validate(cli.overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            // Se imprime directamente: el logging aún no está inicializado
+            eprintln!("❌ {}", e);
+            std::process::exit(2);
+        }
+    };
 
-    info!("🚀 Servidor web iniciando en http://{}", server_address);
+    // El servidor registra a nivel info; los subcomandos solo avisos, para
+    // no mezclar logs con su salida. RUST_LOG tiene prioridad.
+    let serving = matches!(cli.command, None | Some(Command::Serve));
+    init_logging(
+        config.server.log_format,
+        if serving {
+            LevelFilter::INFO
+        } else {
+            LevelFilter::WARN
+        },
+    );
 
-    HttpServer::new(|| {
-        let cors = Cors::permissive();
+    // Archivos de versiones anteriores (sin cadena o con nombre abreviado)
+    if config.cache.backend == CacheBackend::Fs {
+        if let Err(e) = FsAbiCache::new(&config.storage.abi_dir).migrate(config.default_chain_id) {
+            warn!(code = e.code(), error = %e, "no se pudo migrar la caché de ABIs");
+        }
+    }
+
+    match cli.command {
+        None | Some(Command::Serve) => serve(config).await,
+        Some(command) => std::process::exit(cli::run(command, cli.output, &config).await),
+    }
+}
+
+async fn serve(config: AppConfig) -> std::io::Result<()> {
+    let history = match HistoryStore::open(&config.storage.history_db) {
+        Ok(store) => web::Data::new(store),
+        Err(e) => {
+            return Err(std::io::Error::other(format!(
+                "No se pudo abrir la base de datos de historial: {}",
+                e
+            )))
+        }
+    };
+
+    let server_address = config.server.bind.clone();
+    let workers = config.server.workers;
+    let client_request_timeout = Duration::from_secs(config.timeouts.client_request_secs);
+    let upstreams = Upstreams::from_config(&config).map_err(std::io::Error::other)?;
+    let metrics = Arc::new(Metrics::new());
+    let decoder = web::Data::new(
+        instrumented_decoder(&config, &upstreams, &metrics).map_err(std::io::Error::other)?,
+    );
+    let tokens = web::Data::new(
+        TokenResolver::from_config(&config, &upstreams).map_err(std::io::Error::other)?,
+    );
+    let upstreams = web::Data::new(upstreams);
+    let analysis_cache =
+        web::Data::new(AnalysisCache::from_config(&config).map_err(std::io::Error::other)?);
+    let metrics = web::Data::from(metrics);
+    let authenticator = web::Data::new(Authenticator::new(config.auth.clone()));
+    if config.auth.enabled {
+        info!(
+            keys = config.auth.keys.len(),
+            "autenticación por API key activada"
+        );
+    } else {
+        warn!("autenticación desactivada: cualquier cliente puede usar /analysis");
+    }
+    let config = web::Data::new(config);
+
+    info!(bind = %server_address, "servidor web iniciando");
+
+    let mut server = HttpServer::new(move || {
+        let cors = build_cors(&config.server.cors_origins);
 
         App::new()
+            .wrap(from_fn(authenticate))
+            .wrap(from_fn(track_requests))
+            .wrap(from_fn(assign_request_id))
             .wrap(cors)
-            .route("/decode", web::post().to(decode_handler))
-            .route("/analysis", web::post().to(analysis_handler))
+            .app_data(config.clone())
+            .app_data(decoder.clone())
+            .app_data(history.clone())
+            .app_data(upstreams.clone())
+            .app_data(analysis_cache.clone())
+            .app_data(tokens.clone())
+            .app_data(metrics.clone())
+            .app_data(authenticator.clone())
+            .configure(handlers::configure)
     })
-    .bind(server_address)?
-    .run()
-    .await
+    .client_request_timeout(client_request_timeout);
+    if let Some(workers) = workers {
+        server = server.workers(workers);
+    }
+
+    server.bind(server_address)?.run().await
+}
+
+// Logs a stderr en el formato configurado; en JSON cada evento incluye los
+// campos de sus spans (request_id, etapa, contrato...).
+fn init_logging(format: LogFormat, default_level: LevelFilter) {
+    let filter = EnvFilter::builder()
+        .with_default_directive(default_level.into())
+        .from_env_lossy();
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE) // duración de cada etapa al cerrar su span
+        .with_writer(std::io::stderr);
+    match format {
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+        LogFormat::Text => builder.init(),
+    }
+}
+
+fn load_and_validate(overrides: ConfigOverrides) -> Result<AppConfig, AppError> {
+    let mut config = load_app_config(overrides.config.as_deref())?;
+    overrides.apply(&mut config);
+    config.validate()?;
+    Ok(config)
 }
@@ -0,0 +1,238 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use async_trait::async_trait;
+use ethabi::Address;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::decoder::{AbiCache, AbiProvider, CachedAbi, Decoder, FetchedAbi};
+use crate::error::AppError;
+use crate::explorer::ExplorerAbiProvider;
+use crate::store::{abi_cache_from_config, SignatureDb};
+use crate::upstream::Upstreams;
+use crate::AppConfig;
+
+// Niveles de riesgo que se cuentan por separado; cualquier otro texto del LLM
+// cuenta como "unknown" para no disparar la cardinalidad de la métrica.
+const RISK_LEVELS: [&str; 5] = ["low", "medium", "high", "caution", "critical"];
+
+// Métricas Prometheus del servidor, expuestas en GET /metrics
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_latency: HistogramVec,
+    pub abi_cache: IntCounterVec,
+    pub explorer_errors: IntCounterVec,
+    pub llm_latency: Histogram,
+    pub llm_tokens: IntCounterVec,
+    pub risk_levels: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("rdt".to_string()), None)
+            .expect("prefijo de métricas válido");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Peticiones HTTP por endpoint"),
+            &["endpoint", "method", "status"],
+        )
+        .unwrap();
+        let http_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Latencia de las peticiones HTTP por endpoint",
+            ),
+            &["endpoint"],
+        )
+        .unwrap();
+        let abi_cache = IntCounterVec::new(
+            Opts::new(
+                "abi_cache_total",
+                "Consultas a la caché persistente de ABIs (fallos de la caché en memoria)",
+            ),
+            &["result"],
+        )
+        .unwrap();
+        let explorer_errors = IntCounterVec::new(
+            Opts::new(
+                "explorer_errors_total",
+                "Errores al obtener ABIs del explorador",
+            ),
+            &["code"],
+        )
+        .unwrap();
+        let llm_latency = Histogram::with_opts(
+            HistogramOpts::new(
+                "llm_request_duration_seconds",
+                "Latencia de las llamadas al proveedor LLM",
+            )
+            .buckets(vec![0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0]),
+        )
+        .unwrap();
+        let llm_tokens = IntCounterVec::new(
+            Opts::new("llm_tokens_total", "Tokens consumidos en el proveedor LLM"),
+            &["kind"],
+        )
+        .unwrap();
+        let risk_levels = IntCounterVec::new(
+            Opts::new(
+                "risk_level_total",
+                "Análisis completados por nivel de riesgo",
+            ),
+            &["level"],
+        )
+        .unwrap();
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_latency.clone()),
+            Box::new(abi_cache.clone()),
+            Box::new(explorer_errors.clone()),
+            Box::new(llm_latency.clone()),
+            Box::new(llm_tokens.clone()),
+            Box::new(risk_levels.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("métricas sin nombres duplicados");
+        }
+
+        Metrics {
+            registry,
+            http_requests,
+            http_latency,
+            abi_cache,
+            explorer_errors,
+            llm_latency,
+            llm_tokens,
+            risk_levels,
+        }
+    }
+
+    pub fn record_risk_level(&self, risk_level: Option<&str>) {
+        let level = risk_level
+            .map(|level| level.trim().to_lowercase())
+            .filter(|level| RISK_LEVELS.contains(&level.as_str()))
+            .unwrap_or_else(|| "unknown".to_string());
+        self.risk_levels.with_label_values(&[&level]).inc();
+    }
+
+    // Formato de texto de Prometheus
+    pub fn render(&self) -> Result<String, AppError> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        String::from_utf8(buffer).map_err(|e| AppError::Internal(e.to_string()))
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Middleware: cuenta peticiones y latencia por patrón de ruta (p. ej.
+// `/history/{id}`), no por URL concreta.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let endpoint = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let started = Instant::now();
+
+    let res = next.call(req).await?;
+
+    if let Some(metrics) = metrics {
+        metrics
+            .http_requests
+            .with_label_values(&[&endpoint, &method, res.status().as_str()])
+            .inc();
+        metrics
+            .http_latency
+            .with_label_values(&[&endpoint])
+            .observe(started.elapsed().as_secs_f64());
+    }
+    Ok(res)
+}
+
+// Caché de ABIs que cuenta aciertos y fallos de la caché envuelta
+pub struct InstrumentedCache<C> {
+    inner: C,
+    metrics: Arc<Metrics>,
+}
+
+impl<C: AbiCache> AbiCache for InstrumentedCache<C> {
+    fn get(&self, chain_id: u64, address: &Address) -> Result<Option<CachedAbi>, AppError> {
+        let abi = self.inner.get(chain_id, address)?;
+        let result = if abi.is_some() { "hit" } else { "miss" };
+        self.metrics.abi_cache.with_label_values(&[result]).inc();
+        Ok(abi)
+    }
+
+    fn put(&self, entry: &CachedAbi) -> Result<(), AppError> {
+        self.inner.put(entry)
+    }
+
+    fn remove(&self, chain_id: u64, address: &Address) -> Result<bool, AppError> {
+        self.inner.remove(chain_id, address)
+    }
+
+    fn list(&self) -> Result<Vec<CachedAbi>, AppError> {
+        self.inner.list()
+    }
+}
+
+// Proveedor de ABIs que cuenta los errores del proveedor envuelto por código
+pub struct InstrumentedProvider<P> {
+    inner: P,
+    metrics: Arc<Metrics>,
+}
+
+#[async_trait]
+impl<P: AbiProvider> AbiProvider for InstrumentedProvider<P> {
+    async fn fetch_abi(&self, chain_id: u64, address: &Address) -> Result<FetchedAbi, AppError> {
+        self.inner
+            .fetch_abi(chain_id, address)
+            .await
+            .inspect_err(|e| {
+                self.metrics
+                    .explorer_errors
+                    .with_label_values(&[e.code()])
+                    .inc();
+            })
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+// Igual que `explorer::decoder_from_config`, pero con caché y explorador instrumentados
+pub fn instrumented_decoder(
+    config: &AppConfig,
+    upstreams: &Upstreams,
+    metrics: &Arc<Metrics>,
+) -> Result<Decoder, AppError> {
+    Ok(Decoder::new(InstrumentedCache {
+        inner: abi_cache_from_config(config)?,
+        metrics: metrics.clone(),
+    })
+    .with_provider(InstrumentedProvider {
+        inner: ExplorerAbiProvider::from_config(config, upstreams),
+        metrics: metrics.clone(),
+    })
+    .with_ttl(config.abi_ttl())
+    .with_signatures(SignatureDb::from_config(config)?))
+}
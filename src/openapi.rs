@@ -0,0 +1,69 @@
+use utoipa::OpenApi;
+
+use crate::handlers;
+use crate::{
+    AbiCacheEntry, AbiDetailResponse, AbiImportResponse, AbiListResponse, AbiUploadRequest,
+    AnalysisRequest, AnalysisResponse, CallCandidate, DecodeRequest, DecodeResponse, EncodeRequest,
+    EncodeResponse, EncodingFinding, ErrorResponse, FindingKind, HealthCheck, HealthResponse,
+    HistoryEntry, HistoryListResponse, InlineAbi, TokenAmount, TokenMetadata, TokenStandard,
+};
+
+// Especificación OpenAPI 3 generada a partir de los handlers y de los tipos de
+// lib.rs. Se sirve en /openapi.json y con Redoc en /docs.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "rust_decompile_test",
+        description = "Decodificación de llamadas a contratos EVM y análisis de riesgo con LLM"
+    ),
+    paths(
+        handlers::decode_handler,
+        handlers::analysis_handler,
+        handlers::encode_handler,
+        handlers::history_list_handler,
+        handlers::history_entry_handler,
+        handlers::abi_list_handler,
+        handlers::abi_export_handler,
+        handlers::abi_import_handler,
+        handlers::abi_get_handler,
+        handlers::abi_upload_handler,
+        handlers::abi_delete_handler,
+        handlers::abi_refresh_handler,
+        handlers::health_handler,
+        handlers::readiness_handler,
+        handlers::metrics_handler,
+    ),
+    components(schemas(
+        DecodeRequest,
+        InlineAbi,
+        DecodeResponse,
+        CallCandidate,
+        EncodingFinding,
+        FindingKind,
+        TokenMetadata,
+        TokenStandard,
+        TokenAmount,
+        AnalysisRequest,
+        AnalysisResponse,
+        EncodeRequest,
+        EncodeResponse,
+        ErrorResponse,
+        HealthCheck,
+        HealthResponse,
+        HistoryEntry,
+        HistoryListResponse,
+        AbiCacheEntry,
+        AbiListResponse,
+        AbiDetailResponse,
+        AbiUploadRequest,
+        AbiImportResponse,
+    )),
+    tags(
+        (name = "decode", description = "Decodificación de datos de llamada"),
+        (name = "analysis", description = "Análisis de riesgo con LLM"),
+        (name = "history", description = "Historial de decodificaciones y análisis"),
+        (name = "abis", description = "Administración de la caché de ABIs"),
+        (name = "operations", description = "Salud, disponibilidad y métricas"),
+    )
+)]
+pub struct ApiDoc;
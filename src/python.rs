@@ -0,0 +1,188 @@
+use ethabi::ethereum_types::H256;
+use ethabi::{Address, Token};
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyInt, PyList, PyTuple};
+use pyo3::IntoPyObjectExt;
+
+use crate::abi::FsAbiCache;
+use crate::decode;
+use crate::decoder::{load_contract, AbiCache};
+use crate::error::AppError;
+use crate::human_abi::parse_abi_str;
+use crate::AppConfig;
+
+// Módulo de Python (PyO3/maturin) sobre el mismo núcleo de decodificación
+// que usan el servidor y el CLI. Los ABIs se pasan como texto (array ABI,
+// artefacto con campo "abi", firmas legibles o interfaz de Solidity);
+// `load_abi` los lee de la caché `ABI/`.
+
+create_exception!(
+    rust_decompile_test,
+    DecoderError,
+    PyException,
+    "Error de decodificación; args = (código estable, mensaje)"
+);
+
+#[pymodule]
+fn rust_decompile_test(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("DecoderError", m.py().get_type::<DecoderError>())?;
+    m.add_function(wrap_pyfunction!(load_abi, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_function_call, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_log, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_error, m)?)?;
+    m.add_function(wrap_pyfunction!(selector, m)?)?;
+    Ok(())
+}
+
+// Lee un ABI de la caché local; devuelve el JSON como texto
+#[pyfunction]
+#[pyo3(signature = (address, abi_dir = "ABI", chain_id = None))]
+fn load_abi(address: &str, abi_dir: &str, chain_id: Option<u64>) -> PyResult<String> {
+    let contract_address = address
+        .parse::<Address>()
+        .map_err(|e| py_error(AppError::InvalidAddress(format!("{}: {}", address, e))))?;
+    let chain_id = chain_id.unwrap_or_else(|| AppConfig::default().default_chain_id);
+
+    let cached = FsAbiCache::new(abi_dir)
+        .get(chain_id, &contract_address)
+        .map_err(py_error)?
+        .filter(|cached| cached.matches(chain_id, &contract_address))
+        .ok_or_else(|| {
+            py_error(AppError::NotFound(format!(
+                "ABI de {:?} no está en {}",
+                contract_address, abi_dir
+            )))
+        })?;
+    Ok(cached.abi.to_string())
+}
+
+// {"function_name", "signature", "selector", "arguments": [{"name", "type", "value"}]}
+#[pyfunction]
+fn decode_function_call<'py>(
+    py: Python<'py>,
+    abi: &str,
+    call_data: &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let contract = parse_contract(abi)?;
+    let (function, tokens) = decode::decode_function(&contract, call_data).map_err(py_error)?;
+    let decoded = decode::describe_call(function, &tokens);
+
+    let arguments = PyList::empty(py);
+    for (argument, token) in decoded.arguments.iter().zip(&tokens) {
+        let item = PyDict::new(py);
+        item.set_item("name", &argument.name)?;
+        item.set_item("type", &argument.kind)?;
+        item.set_item("value", token_to_py(py, token)?)?;
+        arguments.append(item)?;
+    }
+
+    let result = PyDict::new(py);
+    result.set_item("function_name", decoded.function_name)?;
+    result.set_item("signature", decoded.signature)?;
+    result.set_item("selector", decoded.selector)?;
+    result.set_item("arguments", arguments)?;
+    Ok(result)
+}
+
+// {"name", "params": [{"name", "value"}]}
+#[pyfunction]
+fn decode_log<'py>(
+    py: Python<'py>,
+    abi: &str,
+    topics: Vec<String>,
+    data: &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let contract = parse_contract(abi)?;
+    let topics = topics
+        .iter()
+        .map(|topic| {
+            topic
+                .parse::<H256>()
+                .map_err(|e| py_error(AppError::InvalidHex(format!("{}: {}", topic, e))))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let log = decode::decode_log(&contract, &topics, data).map_err(py_error)?;
+
+    // El nombre del evento se recupera por su topic0 (los anónimos no lo tienen)
+    let name = topics.first().and_then(|topic0| {
+        contract
+            .events()
+            .find(|event| !event.anonymous && event.signature() == *topic0)
+            .map(|event| event.name.clone())
+    });
+
+    let params = PyList::empty(py);
+    for param in &log.params {
+        let item = PyDict::new(py);
+        item.set_item("name", &param.name)?;
+        item.set_item("value", token_to_py(py, &param.value)?)?;
+        params.append(item)?;
+    }
+
+    let result = PyDict::new(py);
+    result.set_item("name", name)?;
+    result.set_item("params", params)?;
+    Ok(result)
+}
+
+// {"name", "signature", "arguments"}: Error(string), Panic(uint256) o error del ABI
+#[pyfunction]
+fn decode_error<'py>(
+    py: Python<'py>,
+    abi: &str,
+    revert_data: &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let contract = parse_contract(abi)?;
+    let decoded = decode::decode_error(&contract, revert_data).map_err(py_error)?;
+
+    let result = PyDict::new(py);
+    result.set_item("name", decoded.name)?;
+    result.set_item("signature", decoded.signature)?;
+    result.set_item("arguments", decoded.arguments)?;
+    Ok(result)
+}
+
+#[pyfunction]
+fn selector(signature: &str) -> PyResult<String> {
+    decode::selector(signature).map_err(py_error)
+}
+
+fn parse_contract(abi: &str) -> PyResult<ethabi::Contract> {
+    let abi = parse_abi_str(abi).map_err(py_error)?;
+    load_contract(&abi).map_err(py_error)
+}
+
+// Valores nativos: enteros como int de Python (precisión arbitraria), tuplas
+// como tuple y arrays como list; direcciones y bytes en hex como en el resto.
+fn token_to_py<'py>(py: Python<'py>, token: &Token) -> PyResult<Bound<'py, PyAny>> {
+    match token {
+        Token::Int(_) | Token::Uint(_) => {
+            py.get_type::<PyInt>().call1((decode::format_token(token),))
+        }
+        Token::Bool(value) => value.into_bound_py_any(py),
+        Token::String(value) => value.into_bound_py_any(py),
+        Token::FixedArray(tokens) | Token::Array(tokens) => {
+            let items = tokens
+                .iter()
+                .map(|token| token_to_py(py, token))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items)?.into_bound_py_any(py)
+        }
+        Token::Tuple(tokens) => {
+            let items = tokens
+                .iter()
+                .map(|token| token_to_py(py, token))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyTuple::new(py, items)?.into_bound_py_any(py)
+        }
+        Token::Address(_) | Token::FixedBytes(_) | Token::Bytes(_) => {
+            decode::format_token(token).into_bound_py_any(py)
+        }
+    }
+}
+
+fn py_error(e: AppError) -> PyErr {
+    DecoderError::new_err((e.code(), e.to_string()))
+}
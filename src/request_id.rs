@@ -0,0 +1,68 @@
+use std::future::Future;
+
+// Cabecera con la que se recibe, se devuelve y se propaga el id de correlación
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// Id de la petición en curso, si la tarea se ejecuta dentro de `scope`
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+pub async fn scope<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+// Añade el id de la petición en curso a una llamada saliente (explorador, LLM)
+pub fn propagate(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match current() {
+        Some(request_id) => request.header(REQUEST_ID_HEADER, request_id),
+        None => request,
+    }
+}
+
+// Middleware: reutiliza el x-request-id del cliente (o genera uno), abre el
+// span raíz de la petición y devuelve el id en la respuesta.
+#[cfg(feature = "server")]
+pub async fn assign_request_id(
+    req: actix_web::dev::ServiceRequest,
+    next: actix_web::middleware::Next<impl actix_web::body::MessageBody>,
+) -> Result<actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>, actix_web::Error> {
+    use actix_web::http::header::{HeaderName, HeaderValue};
+    use tracing::{info, info_span, Instrument};
+
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path()
+    );
+    let started = std::time::Instant::now();
+
+    let mut res = scope(request_id.clone(), next.call(req))
+        .instrument(span.clone())
+        .await?;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    span.in_scope(|| {
+        info!(
+            status = res.status().as_u16(),
+            latency_ms = started.elapsed().as_millis() as u64,
+            "petición completada"
+        )
+    });
+    Ok(res)
+}
@@ -0,0 +1,53 @@
+use ethabi::Address;
+use ethers_core::types::transaction::eip2718::TypedTransaction;
+use ethers_core::types::TransactionRequest;
+use ethers_providers::{Http, Middleware, Provider, ProviderError, RpcError};
+
+use crate::error::AppError;
+use crate::upstream::Upstream;
+
+// Código JSON-RPC con el que los nodos señalan una ejecución revertida
+const EXECUTION_REVERTED: i64 = 3;
+
+// Proveedor ethers de un nodo JSON-RPC. Usa su propio cliente HTTP: el
+// transporte de ethers no admite el `reqwest::Client` compartido.
+pub fn provider(rpc_url: &str) -> Result<Provider<Http>, AppError> {
+    Provider::<Http>::try_from(rpc_url)
+        .map_err(|e| AppError::Internal(format!("rpc_url '{}' no válida: {}", rpc_url, e)))
+}
+
+// eth_call contra el último bloque. Devuelve None si la llamada revierte o
+// no devuelve datos (función inexistente o cuenta sin código); los fallos del
+// nodo son `RpcUnavailable` y se reintentan con la política de `upstream`.
+pub async fn eth_call(
+    upstream: &Upstream,
+    provider: &Provider<Http>,
+    to: &Address,
+    data: &[u8],
+) -> Result<Option<Vec<u8>>, AppError> {
+    let call: TypedTransaction = TransactionRequest::new().to(*to).data(data.to_vec()).into();
+    let call = &call;
+    let result = upstream
+        .execute(|| async move {
+            match tokio::time::timeout(upstream.timeout(), provider.call(call, None)).await {
+                Ok(Ok(bytes)) => Ok(Some(bytes)),
+                Ok(Err(e)) if is_revert(&e) => Ok(None),
+                Ok(Err(e)) => Err(AppError::RpcUnavailable(e.to_string())),
+                Err(_) => Err(AppError::RpcUnavailable(format!(
+                    "eth_call sin respuesta en {} s",
+                    upstream.timeout().as_secs()
+                ))),
+            }
+        })
+        .await?;
+    Ok(result
+        .map(|bytes| bytes.to_vec())
+        .filter(|bytes| !bytes.is_empty()))
+}
+
+// Algunos nodos no usan el código 3 y solo lo indican en el mensaje
+fn is_revert(error: &ProviderError) -> bool {
+    error.as_error_response().is_some_and(|error| {
+        error.code == EXECUTION_REVERTED || error.message.to_lowercase().contains("revert")
+    })
+}
@@ -0,0 +1,564 @@
+use ethabi::{Address, Contract, ParamType};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+use crate::abi::{checksum_address, write_atomic, FsAbiCache};
+use crate::decode::canonical_signature;
+use crate::decoder::{AbiCache, CachedAbi};
+use crate::error::AppError;
+use crate::{AppConfig, CacheBackend};
+
+// Espacios de nombres de las cachés compartidas
+pub const NAMESPACE_ABIS: &str = "abis";
+pub const NAMESPACE_SELECTORS: &str = "selectors";
+pub const NAMESPACE_ANALYSIS: &str = "analysis";
+pub const NAMESPACE_TOKENS: &str = "tokens";
+
+// Almacén clave-valor de una caché. Cada instancia cubre un espacio de
+// nombres; los valores son JSON serializado. Con los backends SQLite y Redis
+// varias réplicas del servidor comparten las mismas entradas.
+pub trait CacheStore: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>, AppError>;
+    fn put(&self, key: &str, value: &str) -> Result<(), AppError>;
+    // Devuelve false si la clave no existía
+    fn remove(&self, key: &str) -> Result<bool, AppError>;
+    fn keys(&self) -> Result<Vec<String>, AppError>;
+    // Como `put`, para entradas que caducan: Redis las borra él mismo
+    // (`SET … EX`); en los demás backends las borra `purge_older_than`
+    fn put_expiring(&self, key: &str, value: &str, ttl: Duration) -> Result<(), AppError>;
+    // Borra las entradas escritas hace más de `age` y devuelve cuántas
+    fn purge_older_than(&self, age: Duration) -> Result<usize, AppError>;
+}
+
+// Ejecuta operaciones síncronas de un almacén desde código async. SQLite,
+// Redis y el disco bloquean el hilo: dentro de un runtime de tokio van al
+// pool de hilos bloqueantes para no detener al worker que atiende otras
+// peticiones; fuera de él se ejecutan directamente.
+pub async fn blocking<T, F>(op: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(any(feature = "explorer", feature = "llm"))]
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        return runtime
+            .spawn_blocking(op)
+            .await
+            .map_err(|e| AppError::Internal(format!("operación de caché interrumpida: {}", e)))?;
+    }
+    op()
+}
+
+// Abre el almacén del backend configurado para un espacio de nombres. En el
+// backend "fs" cada espacio es un subdirectorio de `storage.abi_dir`.
+pub fn open_store(config: &AppConfig, namespace: &str) -> Result<Box<dyn CacheStore>, AppError> {
+    match config.cache.backend {
+        CacheBackend::Fs => Ok(Box::new(FsStore::new(
+            config.storage.abi_dir.join(namespace),
+        ))),
+        #[cfg(feature = "server")]
+        CacheBackend::Sqlite => Ok(Box::new(SqliteStore::open(
+            &config.cache.sqlite_path,
+            namespace,
+        )?)),
+        #[cfg(feature = "redis")]
+        CacheBackend::Redis => Ok(Box::new(RedisStore::open(
+            &config.cache.redis_url,
+            &format!("{}:{}:", config.cache.redis_prefix, namespace),
+            Duration::from_secs(config.timeouts.connect_secs),
+        )?)),
+        #[allow(unreachable_patterns)]
+        backend => Err(AppError::Storage(format!(
+            "backend de caché '{}' no disponible en este build",
+            backend.name()
+        ))),
+    }
+}
+
+// Caché de ABIs del backend configurado. El backend "fs" mantiene el formato
+// de la carpeta `ABI/` (con lectura de archivos antiguos).
+pub fn abi_cache_from_config(config: &AppConfig) -> Result<Box<dyn AbiCache>, AppError> {
+    match config.cache.backend {
+        CacheBackend::Fs => Ok(Box::new(FsAbiCache::new(&config.storage.abi_dir))),
+        _ => Ok(Box::new(StoreAbiCache::new(open_store(
+            config,
+            NAMESPACE_ABIS,
+        )?))),
+    }
+}
+
+// Archivos JSON dentro de un directorio: `<dir>/<clave>.json`. Las claves con
+// `/` se guardan en subdirectorios.
+pub struct FsStore {
+    dir: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FsStore { dir: dir.into() }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf, AppError> {
+        if key.is_empty() || key.starts_with('/') || key.split('/').any(|part| part == "..") {
+            return Err(AppError::Storage(format!(
+                "clave de caché inválida: {}",
+                key
+            )));
+        }
+        Ok(self.dir.join(format!("{}.json", key)))
+    }
+}
+
+impl CacheStore for FsStore {
+    fn get(&self, key: &str) -> Result<Option<String>, AppError> {
+        match fs::read_to_string(self.path_for(key)?) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AppError::Storage(e.to_string())),
+        }
+    }
+
+    fn put(&self, key: &str, value: &str) -> Result<(), AppError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::Storage(e.to_string()))?;
+        }
+        write_atomic(&path, value.as_bytes())
+    }
+
+    fn remove(&self, key: &str) -> Result<bool, AppError> {
+        match fs::remove_file(self.path_for(key)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(AppError::Storage(e.to_string())),
+        }
+    }
+
+    fn keys(&self) -> Result<Vec<String>, AppError> {
+        let mut keys = Vec::new();
+        collect_keys(&self.dir, &self.dir, &mut keys)?;
+        Ok(keys)
+    }
+
+    fn put_expiring(&self, key: &str, value: &str, _ttl: Duration) -> Result<(), AppError> {
+        self.put(key, value)
+    }
+
+    // La antigüedad es la fecha de modificación del archivo
+    fn purge_older_than(&self, age: Duration) -> Result<usize, AppError> {
+        let now = SystemTime::now();
+        let mut purged = 0;
+        for key in self.keys()? {
+            let path = self.path_for(&key)?;
+            let expired = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() > age);
+            if expired && self.remove(&key)? {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+fn collect_keys(root: &Path, dir: &Path, keys: &mut Vec<String>) -> Result<(), AppError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(AppError::Storage(e.to_string())),
+    };
+    for entry in entries {
+        let path = entry.map_err(|e| AppError::Storage(e.to_string()))?.path();
+        if path.is_dir() {
+            collect_keys(root, &path, keys)?;
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            if let Some(key) = path
+                .strip_prefix(root)
+                .ok()
+                .and_then(|relative| relative.with_extension("").to_str().map(str::to_string))
+            {
+                keys.push(key.replace('\\', "/"));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS cache (
+    namespace  TEXT    NOT NULL,
+    key        TEXT    NOT NULL,
+    value      TEXT    NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (namespace, key)
+);
+";
+
+// Tabla `cache` de una base de datos SQLite; varias réplicas en la misma
+// máquina (o en un volumen compartido) pueden usar el mismo archivo
+#[cfg(feature = "server")]
+pub struct SqliteStore {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+    namespace: String,
+}
+
+#[cfg(feature = "server")]
+impl SqliteStore {
+    pub fn open<P: AsRef<Path>>(path: P, namespace: &str) -> Result<Self, AppError> {
+        Self::from_connection(rusqlite::Connection::open(path)?, namespace)
+    }
+
+    pub fn open_in_memory(namespace: &str) -> Result<Self, AppError> {
+        Self::from_connection(rusqlite::Connection::open_in_memory()?, namespace)
+    }
+
+    fn from_connection(conn: rusqlite::Connection, namespace: &str) -> Result<Self, AppError> {
+        // Otras réplicas pueden estar escribiendo: se espera en vez de fallar
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(SQLITE_SCHEMA)?;
+        Ok(SqliteStore {
+            conn: std::sync::Mutex::new(conn),
+            namespace: namespace.to_string(),
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, rusqlite::Connection>, AppError> {
+        self.conn
+            .lock()
+            .map_err(|_| AppError::Storage("Lock de la caché SQLite envenenado".to_string()))
+    }
+}
+
+#[cfg(feature = "server")]
+impl CacheStore for SqliteStore {
+    fn get(&self, key: &str) -> Result<Option<String>, AppError> {
+        use rusqlite::OptionalExtension;
+        Ok(self
+            .lock()?
+            .query_row(
+                "SELECT value FROM cache WHERE namespace = ?1 AND key = ?2",
+                rusqlite::params![self.namespace, key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn put(&self, key: &str, value: &str) -> Result<(), AppError> {
+        let updated_at = chrono::Utc::now().timestamp();
+        self.lock()?.execute(
+            "INSERT INTO cache (namespace, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (namespace, key) DO UPDATE SET value = ?3, updated_at = ?4",
+            rusqlite::params![self.namespace, key, value, updated_at],
+        )?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<bool, AppError> {
+        let removed = self.lock()?.execute(
+            "DELETE FROM cache WHERE namespace = ?1 AND key = ?2",
+            rusqlite::params![self.namespace, key],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, AppError> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare("SELECT key FROM cache WHERE namespace = ?1 ORDER BY key")?;
+        let keys = stmt
+            .query_map([&self.namespace], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(keys)
+    }
+
+    fn put_expiring(&self, key: &str, value: &str, _ttl: Duration) -> Result<(), AppError> {
+        self.put(key, value)
+    }
+
+    fn purge_older_than(&self, age: Duration) -> Result<usize, AppError> {
+        let cutoff = chrono::Utc::now().timestamp() - age.as_secs() as i64;
+        Ok(self.lock()?.execute(
+            "DELETE FROM cache WHERE namespace = ?1 AND updated_at < ?2",
+            rusqlite::params![self.namespace, cutoff],
+        )?)
+    }
+}
+
+// Conexiones a Redis libres que se conservan para reutilizarlas
+#[cfg(feature = "redis")]
+const REDIS_MAX_IDLE: usize = 16;
+
+// Servidor compatible con el protocolo de Redis (Redis, Valkey, KeyDB...).
+// Las claves llevan el prefijo `<redis_prefix>:<espacio>:`. Cada operación
+// toma una conexión libre (o abre otra) y la devuelve al terminar, así que las
+// peticiones concurrentes no se esperan entre sí; tras un error la conexión
+// se descarta.
+#[cfg(feature = "redis")]
+pub struct RedisStore {
+    client: redis::Client,
+    prefix: String,
+    timeout: Duration,
+    idle: std::sync::Mutex<Vec<redis::Connection>>,
+}
+
+#[cfg(feature = "redis")]
+impl RedisStore {
+    pub fn open(url: &str, prefix: &str, timeout: Duration) -> Result<Self, AppError> {
+        Ok(RedisStore {
+            client: redis::Client::open(url)?,
+            prefix: prefix.to_string(),
+            timeout,
+            idle: std::sync::Mutex::new(Vec::new()),
+        })
+    }
+
+    fn query<T: redis::FromRedisValue>(&self, cmd: &redis::Cmd) -> Result<T, AppError> {
+        let mut conn = match self.lock_idle()?.pop() {
+            Some(conn) => conn,
+            None => {
+                let conn = self.client.get_connection_with_timeout(self.timeout)?;
+                conn.set_read_timeout(Some(self.timeout))?;
+                conn.set_write_timeout(Some(self.timeout))?;
+                conn
+            }
+        };
+        let result = cmd.query(&mut conn);
+        if result.is_ok() {
+            let mut idle = self.lock_idle()?;
+            if idle.len() < REDIS_MAX_IDLE {
+                idle.push(conn);
+            }
+        }
+        Ok(result?)
+    }
+
+    fn lock_idle(&self) -> Result<std::sync::MutexGuard<'_, Vec<redis::Connection>>, AppError> {
+        self.idle
+            .lock()
+            .map_err(|_| AppError::Storage("Lock de las conexiones a Redis envenenado".to_string()))
+    }
+}
+
+#[cfg(feature = "redis")]
+impl CacheStore for RedisStore {
+    fn get(&self, key: &str) -> Result<Option<String>, AppError> {
+        self.query(redis::cmd("GET").arg(format!("{}{}", self.prefix, key)))
+    }
+
+    fn put(&self, key: &str, value: &str) -> Result<(), AppError> {
+        self.query(
+            redis::cmd("SET")
+                .arg(format!("{}{}", self.prefix, key))
+                .arg(value),
+        )
+    }
+
+    fn remove(&self, key: &str) -> Result<bool, AppError> {
+        let removed: u64 = self.query(redis::cmd("DEL").arg(format!("{}{}", self.prefix, key)))?;
+        Ok(removed > 0)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, AppError> {
+        let pattern = format!("{}*", self.prefix);
+        let mut keys = Vec::new();
+        let mut cursor = 0u64;
+        loop {
+            let (next, batch): (u64, Vec<String>) = self.query(
+                redis::cmd("SCAN")
+                    .arg(cursor)
+                    .arg("MATCH")
+                    .arg(&pattern)
+                    .arg("COUNT")
+                    .arg(500),
+            )?;
+            keys.extend(
+                batch
+                    .iter()
+                    .filter_map(|key| key.strip_prefix(&self.prefix))
+                    .map(str::to_string),
+            );
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        keys.sort();
+        keys.dedup(); // SCAN puede repetir claves
+        Ok(keys)
+    }
+
+    fn put_expiring(&self, key: &str, value: &str, ttl: Duration) -> Result<(), AppError> {
+        self.query(
+            redis::cmd("SET")
+                .arg(format!("{}{}", self.prefix, key))
+                .arg(value)
+                .arg("EX")
+                .arg(ttl.as_secs().max(1)),
+        )
+    }
+
+    // Las claves con `EX` ya caducan en el servidor
+    fn purge_older_than(&self, _age: Duration) -> Result<usize, AppError> {
+        Ok(0)
+    }
+}
+
+// Caché de ABIs sobre un `CacheStore`: clave `<chain_id>/<dirección con
+// checksum>` y `CachedAbi` serializado como valor
+pub struct StoreAbiCache {
+    store: Box<dyn CacheStore>,
+}
+
+impl StoreAbiCache {
+    pub fn new(store: Box<dyn CacheStore>) -> Self {
+        StoreAbiCache { store }
+    }
+}
+
+fn abi_key(chain_id: u64, address: &Address) -> String {
+    format!("{}/{}", chain_id, checksum_address(address))
+}
+
+impl AbiCache for StoreAbiCache {
+    fn get(&self, chain_id: u64, address: &Address) -> Result<Option<CachedAbi>, AppError> {
+        self.store
+            .get(&abi_key(chain_id, address))?
+            .map(|value| {
+                serde_json::from_str(&value).map_err(|e| AppError::InvalidAbi(e.to_string()))
+            })
+            .transpose()
+    }
+
+    fn put(&self, entry: &CachedAbi) -> Result<(), AppError> {
+        let chain_id = entry
+            .chain_id
+            .ok_or_else(|| AppError::Storage(format!("ABI de {:?} sin cadena", entry.address)))?;
+        let value =
+            serde_json::to_string(entry).map_err(|e| AppError::InvalidAbi(e.to_string()))?;
+        self.store.put(&abi_key(chain_id, &entry.address), &value)
+    }
+
+    fn remove(&self, chain_id: u64, address: &Address) -> Result<bool, AppError> {
+        self.store.remove(&abi_key(chain_id, address))
+    }
+
+    fn list(&self) -> Result<Vec<CachedAbi>, AppError> {
+        let mut entries = Vec::new();
+        for key in self.store.keys()? {
+            let Some(value) = self.store.get(&key)? else {
+                continue; // Borrada entre keys() y get()
+            };
+            match serde_json::from_str(&value) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!(key = %key, error = %e, "entrada de ABI ilegible, se omite"),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+// Base de firmas: selector (4 bytes) o topic de evento (32 bytes) en hex ->
+// firmas conocidas, alimentada con cada ABI que pasa por el decoder. Sirve para
+// orientar cuando el ABI de un contrato no contiene el selector de la llamada.
+pub struct SignatureDb {
+    store: Box<dyn CacheStore>,
+}
+
+impl SignatureDb {
+    pub fn new(store: Box<dyn CacheStore>) -> Self {
+        SignatureDb { store }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Result<Self, AppError> {
+        Ok(Self::new(open_store(config, NAMESPACE_SELECTORS)?))
+    }
+
+    // Añade las firmas de funciones, errores y eventos del contrato. La
+    // lectura-modificación-escritura no es atómica entre réplicas: en el peor
+    // caso se pierde una firma que se volverá a añadir con el siguiente ABI.
+    pub fn record(&self, contract: &Contract) -> Result<(), AppError> {
+        let signature_of = |name: &str, params: &[ethabi::Param]| {
+            let kinds: Vec<ParamType> = params.iter().map(|param| param.kind.clone()).collect();
+            canonical_signature(name, &kinds)
+        };
+        let mut signatures: Vec<(String, String)> = Vec::new();
+        for function in contract.functions() {
+            signatures.push((
+                hex::encode(function.short_signature()),
+                signature_of(&function.name, &function.inputs),
+            ));
+        }
+        for error in contract.errors() {
+            signatures.push((
+                hex::encode(&error.signature().as_bytes()[..4]),
+                signature_of(&error.name, &error.inputs),
+            ));
+        }
+        for event in contract.events() {
+            let kinds: Vec<ParamType> = event
+                .inputs
+                .iter()
+                .map(|param| param.kind.clone())
+                .collect();
+            signatures.push((
+                hex::encode(event.signature().as_bytes()),
+                canonical_signature(&event.name, &kinds),
+            ));
+        }
+
+        for (selector, signature) in signatures {
+            self.merge(&selector, [signature])?;
+        }
+        Ok(())
+    }
+
+    // Añade firmas a las ya conocidas para un selector o topic en hex sin 0x
+    pub fn merge(
+        &self,
+        selector: &str,
+        signatures: impl IntoIterator<Item = String>,
+    ) -> Result<(), AppError> {
+        let mut known = self.lookup_hex(selector)?;
+        let before = known.len();
+        known.extend(signatures);
+        if known.len() > before {
+            let value =
+                serde_json::to_string(&known).map_err(|e| AppError::Internal(e.to_string()))?;
+            self.store.put(selector, &value)?;
+        }
+        Ok(())
+    }
+
+    // Toda la base, ordenada por selector
+    pub fn entries(&self) -> Result<BTreeMap<String, Vec<String>>, AppError> {
+        let mut entries = BTreeMap::new();
+        for selector in self.store.keys()? {
+            let known = self.lookup_hex(&selector)?;
+            if !known.is_empty() {
+                entries.insert(selector, known.into_iter().collect());
+            }
+        }
+        Ok(entries)
+    }
+
+    // Firmas conocidas para un selector o topic en hex (con o sin 0x)
+    pub fn lookup(&self, selector: &str) -> Result<Vec<String>, AppError> {
+        let selector = selector.trim_start_matches("0x").to_lowercase();
+        if selector.is_empty() || hex::decode(&selector).is_err() {
+            return Ok(Vec::new());
+        }
+        Ok(self.lookup_hex(&selector)?.into_iter().collect())
+    }
+
+    fn lookup_hex(&self, selector: &str) -> Result<BTreeSet<String>, AppError> {
+        Ok(self
+            .store
+            .get(selector)?
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,355 @@
+use ethabi::{Function, ParamType, Token};
+use std::ops::Range;
+
+use crate::decode::parse_hex;
+use crate::error::AppError;
+use crate::human_abi::check_param_type;
+use crate::{EncodingFinding, FindingKind};
+
+// Tope de hallazgos por llamada: un array largo con relleno sucio en cada
+// elemento daría un hallazgo por palabra
+const MAX_FINDINGS: usize = 32;
+
+const WORD: usize = 32;
+
+// Qué representa cada palabra de la codificación canónica
+enum Slot {
+    Value(ParamType),
+    Offset { in_range: bool }, // false si apunta fuera de la call data recibida
+    Length,
+    Data(usize), // Bytes de contenido de la palabra; el resto es relleno
+}
+
+// Modo estricto: ethabi acepta al decodificar bits sucios en el relleno,
+// offsets no canónicos y datos sobrantes. Se recodifican los argumentos y se
+// compara palabra a palabra con la call data recibida. uintN e intN se
+// decodifican con la palabra entera, así que su recodificación repite los bits
+// altos sucios: esas palabras se comprueban por separado. Un bool con bytes
+// altos no nulos ya lo rechaza ethabi al decodificar (no llega aquí); uno con
+// el último byte distinto de 0/1 es un `NonCanonicalValue`.
+pub fn check_encoding(
+    function: &Function,
+    tokens: &[Token],
+    call_data: &str,
+) -> Result<Vec<EncodingFinding>, AppError> {
+    // Las funciones de `load_contract` ya vienen validadas, pero una construida
+    // a mano podría traer un ancho imposible (`uint512`) para `integer_fits`
+    for param in &function.inputs {
+        check_param_type(&param.kind)?;
+    }
+    let bytes = parse_hex(call_data)?;
+    let input = bytes.get(4..).unwrap_or_default();
+    let canonical = ethabi::encode(tokens);
+
+    let kinds: Vec<ParamType> = function.inputs.iter().map(|p| p.kind.clone()).collect();
+    let paths = function
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            if param.name.is_empty() {
+                format!("#{}", i)
+            } else {
+                param.name.clone()
+            }
+        })
+        .collect();
+    let mut layout = Layout {
+        canonical: &canonical,
+        input,
+        slots: Vec::new(),
+    };
+    layout.tuple(
+        &kinds,
+        paths,
+        Pos {
+            canonical: 0,
+            input: 0,
+        },
+    );
+    let mut slots = layout.slots;
+    slots.sort_by_key(|(pos, _, _)| pos.input);
+
+    let mut findings = Vec::new();
+    // Final de la última palabra que la decodificación ha leído
+    let mut end = 0;
+    for (pos, path, slot) in slots {
+        let got = input.get(pos.input..pos.input + WORD);
+        let expected = canonical.get(pos.canonical..pos.canonical + WORD);
+        let (Some(got), Some(expected)) = (got, expected) else {
+            continue;
+        };
+        end = end.max(pos.input + WORD);
+        if let Slot::Value(kind @ (ParamType::Uint(_) | ParamType::Int(_))) = &slot {
+            if !integer_fits(kind, got) {
+                findings.push(EncodingFinding {
+                    kind: FindingKind::DirtyPadding,
+                    path: Some(path),
+                    offset: pos.input + 4,
+                    detail: format!(
+                        "bits altos no canónicos en {}: 0x{}",
+                        kind,
+                        hex::encode(got)
+                    ),
+                });
+            }
+            continue;
+        }
+        if let Slot::Offset { in_range: false } = slot {
+            findings.push(EncodingFinding {
+                kind: FindingKind::NonCanonicalOffset,
+                path: Some(path),
+                offset: pos.input + 4,
+                detail: format!(
+                    "offset {} fuera de la call data ({} bytes de argumentos)",
+                    word_value(got),
+                    input.len()
+                ),
+            });
+            continue;
+        }
+        if got == expected {
+            continue;
+        }
+        let padding = match &slot {
+            Slot::Value(kind) => padding_of(kind),
+            Slot::Data(used) => *used..WORD,
+            Slot::Offset { .. } | Slot::Length => 0..0,
+        };
+        let only_padding = (0..WORD)
+            .filter(|&i| got[i] != expected[i])
+            .all(|i| padding.contains(&i));
+        let (kind, detail) = match slot {
+            _ if only_padding => (
+                FindingKind::DirtyPadding,
+                format!("relleno no nulo: 0x{}", hex::encode(got)),
+            ),
+            Slot::Value(kind) => (
+                FindingKind::NonCanonicalValue,
+                format!("valor {} no canónico: 0x{}", kind, hex::encode(got)),
+            ),
+            Slot::Offset { .. } => (
+                FindingKind::NonCanonicalOffset,
+                format!(
+                    "offset {} en lugar de {}",
+                    word_value(got),
+                    word_value(expected)
+                ),
+            ),
+            Slot::Length => (
+                FindingKind::NonCanonicalLength,
+                format!(
+                    "longitud {} en lugar de {}",
+                    word_value(got),
+                    word_value(expected)
+                ),
+            ),
+            Slot::Data(_) => (
+                FindingKind::NonCanonicalValue,
+                format!(
+                    "contenido distinto del decodificado: 0x{}",
+                    hex::encode(got)
+                ),
+            ),
+        };
+        findings.push(EncodingFinding {
+            kind,
+            path: Some(path),
+            offset: pos.input + 4,
+            detail,
+        });
+    }
+
+    let end = end.max(canonical.len());
+    let trailing = if input.len() > end {
+        Some(EncodingFinding {
+            kind: FindingKind::ExtraBytes,
+            path: None,
+            offset: end + 4,
+            detail: format!("{} bytes tras los argumentos", input.len() - end),
+        })
+    } else if input.len() < canonical.len() {
+        Some(EncodingFinding {
+            kind: FindingKind::MissingBytes,
+            path: None,
+            offset: input.len() + 4,
+            detail: format!(
+                "faltan {} bytes respecto a la codificación canónica",
+                canonical.len() - input.len()
+            ),
+        })
+    } else {
+        None
+    };
+    // El hallazgo final cuenta dentro del tope
+    findings.truncate(MAX_FINDINGS - usize::from(trailing.is_some()));
+    findings.extend(trailing);
+    Ok(findings)
+}
+
+// Posición de una palabra en la codificación canónica y en la recibida. Se
+// siguen los offsets de cada una por separado, de modo que un offset no
+// canónico no desplaza las comparaciones del contenido al que apunta.
+#[derive(Clone, Copy)]
+struct Pos {
+    canonical: usize,
+    input: usize,
+}
+
+impl Pos {
+    fn add(self, bytes: usize) -> Pos {
+        Pos {
+            canonical: self.canonical + bytes,
+            input: self.input + bytes,
+        }
+    }
+}
+
+struct Layout<'a> {
+    canonical: &'a [u8],
+    input: &'a [u8],
+    slots: Vec<(Pos, String, Slot)>,
+}
+
+impl Layout<'_> {
+    // Parámetros codificados como tupla desde `base`: cabeceras con los
+    // estáticos en línea y offsets (relativos a `base`) hacia los dinámicos
+    fn tuple(&mut self, kinds: &[ParamType], paths: Vec<String>, base: Pos) {
+        let mut head = base;
+        for (kind, path) in kinds.iter().zip(paths) {
+            if kind.is_dynamic() {
+                let offsets = (
+                    read_usize(self.canonical, head.canonical),
+                    read_usize(self.input, head.input),
+                );
+                // Un offset que no deja sitio ni para una palabra no se sigue
+                let target = match offsets {
+                    (Some(canonical), Some(input))
+                        if base.input + input + WORD <= self.input.len() =>
+                    {
+                        Some(Pos {
+                            canonical: base.canonical + canonical,
+                            input: base.input + input,
+                        })
+                    }
+                    _ => None,
+                };
+                let in_range = target.is_some() || head.input + WORD > self.input.len();
+                self.slots
+                    .push((head, path.clone(), Slot::Offset { in_range }));
+                if let Some(target) = target {
+                    self.dynamic(kind, path, target);
+                }
+                head = head.add(WORD);
+            } else {
+                self.fixed(kind, path, head);
+                head = head.add(static_size(kind));
+            }
+        }
+    }
+
+    fn fixed(&mut self, kind: &ParamType, path: String, pos: Pos) {
+        match kind {
+            ParamType::Tuple(kinds) => {
+                let paths = component_paths(&path, kinds.len());
+                self.tuple(kinds, paths, pos);
+            }
+            ParamType::FixedArray(inner, len) => {
+                let kinds = vec![(**inner).clone(); *len];
+                self.tuple(&kinds, element_paths(&path, *len), pos);
+            }
+            other => self.slots.push((pos, path, Slot::Value(other.clone()))),
+        }
+    }
+
+    fn dynamic(&mut self, kind: &ParamType, path: String, pos: Pos) {
+        match kind {
+            ParamType::Bytes | ParamType::String => {
+                self.slots.push((pos, path.clone(), Slot::Length));
+                let len = read_usize(self.canonical, pos.canonical).unwrap_or_default();
+                for (i, start) in (0..len).step_by(WORD).enumerate() {
+                    let used = (len - start).min(WORD);
+                    self.slots
+                        .push((pos.add(WORD * (i + 1)), path.clone(), Slot::Data(used)));
+                }
+            }
+            ParamType::Array(inner) => {
+                self.slots.push((pos, path.clone(), Slot::Length));
+                let len = read_usize(self.canonical, pos.canonical).unwrap_or_default();
+                let kinds = vec![(**inner).clone(); len];
+                self.tuple(&kinds, element_paths(&path, len), pos.add(WORD));
+            }
+            ParamType::FixedArray(inner, len) => {
+                let kinds = vec![(**inner).clone(); *len];
+                self.tuple(&kinds, element_paths(&path, *len), pos);
+            }
+            ParamType::Tuple(kinds) => {
+                let paths = component_paths(&path, kinds.len());
+                self.tuple(kinds, paths, pos);
+            }
+            other => self.slots.push((pos, path, Slot::Value(other.clone()))),
+        }
+    }
+}
+
+fn static_size(kind: &ParamType) -> usize {
+    match kind {
+        ParamType::Tuple(kinds) => kinds.iter().map(static_size).sum(),
+        ParamType::FixedArray(inner, len) => static_size(inner) * len,
+        _ => WORD,
+    }
+}
+
+// Bytes de relleno de una palabra con un valor estático (los enteros se
+// comprueban con `integer_fits`)
+fn padding_of(kind: &ParamType) -> Range<usize> {
+    match kind {
+        ParamType::Address => 0..12,
+        ParamType::Bool => 0..WORD - 1,
+        ParamType::FixedBytes(len) => *len..WORD,
+        _ => 0..0,
+    }
+}
+
+// uintN sin bits por encima de N; intN con los bits altos iguales a la
+// extensión de signo de los N bajos
+fn integer_fits(kind: &ParamType, word: &[u8]) -> bool {
+    let (bits, signed) = match kind {
+        ParamType::Uint(bits) => (*bits, false),
+        ParamType::Int(bits) => (*bits, true),
+        _ => return true,
+    };
+    let (high, low) = word.split_at(WORD - bits / 8);
+    let fill = if signed && low.first().is_some_and(|b| b & 0x80 != 0) {
+        0xff
+    } else {
+        0x00
+    };
+    high.iter().all(|b| *b == fill)
+}
+
+// Los componentes de una tupla no tienen nombre en `ParamType`: se usa el índice
+fn component_paths(path: &str, len: usize) -> Vec<String> {
+    (0..len).map(|i| format!("{}.{}", path, i)).collect()
+}
+
+fn element_paths(path: &str, len: usize) -> Vec<String> {
+    (0..len).map(|i| format!("{}[{}]", path, i)).collect()
+}
+
+fn read_usize(encoded: &[u8], pos: usize) -> Option<usize> {
+    let word = encoded.get(pos..pos + WORD)?;
+    let (high, low) = word.split_at(WORD - 8);
+    if high.iter().any(|b| *b != 0) {
+        return None;
+    }
+    usize::try_from(u64::from_be_bytes(low.try_into().ok()?)).ok()
+}
+
+// Valor de una palabra para los mensajes: decimal si cabe en u64
+fn word_value(word: &[u8]) -> String {
+    match read_usize(word, 0) {
+        Some(value) => value.to_string(),
+        None => format!("0x{}", hex::encode(word)),
+    }
+}
@@ -0,0 +1,324 @@
+use ethabi::ethereum_types::U256;
+use ethabi::{Address, Function, Token};
+
+use crate::decode::function_signature;
+use crate::{TokenAmount, TokenMetadata, TokenStandard};
+
+// Firmas con un importe del propio contrato llamado, y la posición del
+// argumento. En un ERC-721 ese argumento es el id del NFT.
+const TOKEN_AMOUNT_ARGUMENTS: [(&str, usize); 9] = [
+    ("transfer(address,uint256)", 1),
+    ("transferFrom(address,address,uint256)", 2),
+    ("approve(address,uint256)", 1),
+    ("increaseAllowance(address,uint256)", 1),
+    ("decreaseAllowance(address,uint256)", 1),
+    (
+        "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+        2,
+    ),
+    ("mint(address,uint256)", 1),
+    ("burn(uint256)", 0),
+    ("withdraw(uint256)", 0),
+];
+
+// Transferencias ERC-721 cuyo resumen nombra la colección
+const NFT_SIGNATURES: [&str; 2] = [
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+];
+
+// Si merece la pena leer los metadatos del contrato llamado por RPC
+pub fn uses_token_metadata(function: &Function) -> bool {
+    let signature = function_signature(function);
+    amount_argument(&signature).is_some() || NFT_SIGNATURES.contains(&signature.as_str())
+}
+
+fn amount_argument(signature: &str) -> Option<usize> {
+    TOKEN_AMOUNT_ARGUMENTS
+        .iter()
+        .find(|(known, _)| *known == signature)
+        .map(|(_, index)| *index)
+}
+
+// Importes del token en los argumentos de la llamada. Vacío si no se conocen
+// los decimales o el contrato es una colección de NFTs.
+pub fn token_amounts(
+    function: &Function,
+    tokens: &[Token],
+    token: Option<&TokenMetadata>,
+) -> Vec<TokenAmount> {
+    let Some(token) = token.filter(|token| token.decimals.is_some() && !is_nft(Some(token))) else {
+        return Vec::new();
+    };
+    let Some(index) = amount_argument(&function_signature(function)) else {
+        return Vec::new();
+    };
+    match (tokens.get(index), function.inputs.get(index)) {
+        (Some(Token::Uint(value)), Some(param)) => vec![TokenAmount {
+            index,
+            argument: if param.name.is_empty() {
+                format!("#{}", index)
+            } else {
+                param.name.clone()
+            },
+            raw: value.to_string(),
+            formatted: format_amount(value, Some(token)),
+        }],
+        _ => Vec::new(),
+    }
+}
+
+// Añade a cada argumento su importe en unidades (`Uint(1500000) = 1.5 USDC`),
+// para el prompt del LLM
+pub fn annotate_arguments(arguments: &[String], amounts: &[TokenAmount]) -> Vec<String> {
+    arguments
+        .iter()
+        .enumerate()
+        .map(
+            |(i, argument)| match amounts.iter().find(|amount| amount.index == i) {
+                Some(amount) => format!("{} = {}", argument, amount.formatted),
+                None => argument.clone(),
+            },
+        )
+        .collect()
+}
+
+// Descripción en una línea de una llamada a un estándar conocido (ERC-20,
+// ERC-721, ERC-1155, WETH, Ownable, AccessControl, proxies UUPS), generada
+// con plantillas por firma. `token` son los metadatos del contrato llamado;
+// sin ellos los importes se muestran en unidades base.
+pub fn summarize(
+    function: &Function,
+    tokens: &[Token],
+    token: Option<&TokenMetadata>,
+) -> Option<String> {
+    let amount = |value: &U256| format_amount(value, token);
+    let symbol = token
+        .and_then(|token| token.symbol.clone())
+        .unwrap_or_else(|| "el token".to_string());
+    let nft = |id: &U256| match token.and_then(|token| token.symbol.as_deref()) {
+        Some(collection) => format!("el NFT {} #{}", collection, id),
+        None => format!("el NFT #{}", id),
+    };
+
+    let summary = match (function_signature(function).as_str(), tokens) {
+        // En un ERC-721 el uint256 de transferFrom, approve, mint y burn es el id
+        (
+            "transferFrom(address,address,uint256)",
+            [Token::Address(from), Token::Address(to), Token::Uint(id)],
+        ) if is_nft(token) => format!(
+            "Transferir {} de {} a {}",
+            nft(id),
+            short_address(from),
+            short_address(to)
+        ),
+        ("approve(address,uint256)", [Token::Address(spender), Token::Uint(id)])
+            if is_nft(token) =>
+        {
+            if spender.is_zero() {
+                format!("Revocar la aprobación sobre {}", nft(id))
+            } else {
+                format!(
+                    "Aprobar a {} para transferir {}",
+                    short_address(spender),
+                    nft(id)
+                )
+            }
+        }
+        ("mint(address,uint256)", [Token::Address(to), Token::Uint(id)]) if is_nft(token) => {
+            format!("Acuñar {} para {}", nft(id), short_address(to))
+        }
+        ("burn(uint256)", [Token::Uint(id)]) if is_nft(token) => format!("Quemar {}", nft(id)),
+        ("transfer(address,uint256)", [Token::Address(to), Token::Uint(value)]) => {
+            format!("Transferir {} a {}", amount(value), short_address(to))
+        }
+        (
+            "transferFrom(address,address,uint256)",
+            [Token::Address(from), Token::Address(to), Token::Uint(value)],
+        ) => format!(
+            "Transferir {} de {} a {}",
+            amount(value),
+            short_address(from),
+            short_address(to)
+        ),
+        ("approve(address,uint256)", [Token::Address(spender), Token::Uint(value)]) => {
+            match value {
+                _ if value.is_zero() => {
+                    format!(
+                        "Revocar la aprobación de {} sobre {}",
+                        short_address(spender),
+                        symbol
+                    )
+                }
+                _ if *value == U256::MAX => format!(
+                    "Aprobar a {} para gastar {} ILIMITADO",
+                    short_address(spender),
+                    symbol
+                ),
+                _ => format!(
+                    "Aprobar a {} para gastar {}",
+                    short_address(spender),
+                    amount(value)
+                ),
+            }
+        }
+        ("increaseAllowance(address,uint256)", [Token::Address(spender), Token::Uint(value)]) => {
+            format!(
+                "Aumentar en {} lo que {} puede gastar",
+                amount(value),
+                short_address(spender)
+            )
+        }
+        ("decreaseAllowance(address,uint256)", [Token::Address(spender), Token::Uint(value)]) => {
+            format!(
+                "Reducir en {} lo que {} puede gastar",
+                amount(value),
+                short_address(spender)
+            )
+        }
+        (
+            "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+            [Token::Address(owner), Token::Address(spender), Token::Uint(value), Token::Uint(deadline), ..],
+        ) => format!(
+            "Permiso firmado de {} para que {} gaste {} (válido hasta {})",
+            short_address(owner),
+            short_address(spender),
+            if *value == U256::MAX {
+                format!("{} ILIMITADO", symbol)
+            } else {
+                amount(value)
+            },
+            deadline
+        ),
+        ("mint(address,uint256)", [Token::Address(to), Token::Uint(value)]) => {
+            format!("Acuñar {} para {}", amount(value), short_address(to))
+        }
+        ("burn(uint256)", [Token::Uint(value)]) => format!("Quemar {}", amount(value)),
+        ("deposit()", []) => "Depositar ETH a cambio de tokens envueltos".to_string(),
+        ("withdraw(uint256)", [Token::Uint(value)]) => format!("Retirar {}", amount(value)),
+        (
+            "safeTransferFrom(address,address,uint256)"
+            | "safeTransferFrom(address,address,uint256,bytes)",
+            [Token::Address(from), Token::Address(to), Token::Uint(id), ..],
+        ) => format!(
+            "Transferir {} de {} a {}",
+            nft(id),
+            short_address(from),
+            short_address(to)
+        ),
+        (
+            "safeTransferFrom(address,address,uint256,uint256,bytes)",
+            [Token::Address(from), Token::Address(to), Token::Uint(id), Token::Uint(value), _],
+        ) => format!(
+            "Transferir {} unidades del token #{} de {} a {}",
+            value,
+            id,
+            short_address(from),
+            short_address(to)
+        ),
+        (
+            "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+            [Token::Address(from), Token::Address(to), Token::Array(ids), ..],
+        ) => format!(
+            "Transferir {} tipos de token de {} a {}",
+            ids.len(),
+            short_address(from),
+            short_address(to)
+        ),
+        ("setApprovalForAll(address,bool)", [Token::Address(operator), Token::Bool(approved)]) => {
+            if *approved {
+                format!(
+                    "Autorizar a {} a mover TODOS los tokens de la colección",
+                    short_address(operator)
+                )
+            } else {
+                format!(
+                    "Retirar a {} la autorización sobre la colección",
+                    short_address(operator)
+                )
+            }
+        }
+        ("transferOwnership(address)", [Token::Address(owner)]) => format!(
+            "Transferir la propiedad del contrato a {}",
+            short_address(owner)
+        ),
+        ("renounceOwnership()", []) => {
+            "Renunciar a la propiedad del contrato (queda sin dueño)".to_string()
+        }
+        ("grantRole(bytes32,address)", [Token::FixedBytes(role), Token::Address(account)]) => {
+            format!(
+                "Conceder el rol {} a {}",
+                role_name(role),
+                short_address(account)
+            )
+        }
+        ("revokeRole(bytes32,address)", [Token::FixedBytes(role), Token::Address(account)]) => {
+            format!(
+                "Revocar el rol {} a {}",
+                role_name(role),
+                short_address(account)
+            )
+        }
+        (
+            "upgradeTo(address)" | "upgradeToAndCall(address,bytes)",
+            [Token::Address(implementation), ..],
+        ) => {
+            format!(
+                "Actualizar la implementación del proxy a {}",
+                short_address(implementation)
+            )
+        }
+        ("pause()", []) => "Pausar el contrato".to_string(),
+        ("unpause()", []) => "Reanudar el contrato".to_string(),
+        _ => return None,
+    };
+    Some(summary)
+}
+
+fn is_nft(token: Option<&TokenMetadata>) -> bool {
+    token.is_some_and(|token| token.standard == Some(TokenStandard::Erc721))
+}
+
+// Importe en unidades del token (`1.5 WETH`) si se conocen sus decimales; si
+// no, en unidades base
+pub fn format_amount(value: &U256, token: Option<&TokenMetadata>) -> String {
+    let decimals = token.and_then(|token| token.decimals);
+    let symbol = token.and_then(|token| token.symbol.as_deref());
+    match (decimals, symbol) {
+        (Some(decimals), Some(symbol)) => format!("{} {}", format_units(value, decimals), symbol),
+        (Some(decimals), None) => format!("{} unidades", format_units(value, decimals)),
+        (None, Some(symbol)) => format!("{} unidades base de {}", value, symbol),
+        (None, None) => format!("{} unidades base", value),
+    }
+}
+
+// `value / 10^decimals` en decimal exacto, sin ceros finales
+pub fn format_units(value: &U256, decimals: u8) -> String {
+    let digits = value.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+// `0x742d…2700`: suficiente para reconocer la dirección en una línea
+pub fn short_address(address: &Address) -> String {
+    let full = format!("{:?}", address);
+    format!("{}…{}", &full[..6], &full[full.len() - 4..])
+}
+
+fn role_name(role: &[u8]) -> String {
+    if role.iter().all(|b| *b == 0) {
+        "DEFAULT_ADMIN_ROLE".to_string()
+    } else {
+        format!("0x{}…", hex::encode(&role[..4]))
+    }
+}
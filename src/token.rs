@@ -0,0 +1,216 @@
+use ethabi::{Address, ParamType, Token};
+use ethers_providers::{Http, Provider};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::abi::checksum_address;
+use crate::error::AppError;
+use crate::rpc::{eth_call, provider};
+use crate::store::{blocking, open_store, CacheStore, NAMESPACE_TOKENS};
+use crate::upstream::{Upstream, Upstreams};
+use crate::{AppConfig, ChainConfig, TokenMetadata, TokenStandard};
+
+// Identificadores ERC-165 de las interfaces de colecciones
+const ERC721_INTERFACE: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+const ERC1155_INTERFACE: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+// Lee los metadatos de un token (ERC-20, ERC-721 o ERC-1155) con eth_call en
+// el nodo RPC de su cadena (`rpc_url` del registro de cadenas, opcional). Las
+// consultas van en paralelo y con un plazo total (`token_metadata_secs`).
+// El resultado se guarda sin caducidad en la caché compartida, con clave
+// `<chain_id>/<dirección con checksum>`, también cuando el contrato no es un
+// token (valor `null`), para no repetir las llamadas en cada petición. Los
+// fallos de la caché solo se registran.
+pub struct TokenResolver {
+    providers: HashMap<u64, Provider<Http>>, // Por chain_id, solo cadenas con rpc_url
+    upstream: Arc<Upstream>,
+    store: Arc<dyn CacheStore>,
+    deadline: Duration,
+}
+
+impl TokenResolver {
+    pub fn new(
+        chains: Vec<ChainConfig>,
+        upstream: Arc<Upstream>,
+        store: Box<dyn CacheStore>,
+        deadline: Duration,
+    ) -> Self {
+        let providers = chains
+            .iter()
+            .filter_map(|chain| {
+                let rpc_url = chain.rpc_url.as_deref()?;
+                match provider(rpc_url) {
+                    Ok(provider) => Some((chain.chain_id, provider)),
+                    Err(e) => {
+                        warn!(chain_id = chain.chain_id, error = %e, "cadena sin metadatos de tokens");
+                        None
+                    }
+                }
+            })
+            .collect();
+        TokenResolver {
+            providers,
+            upstream,
+            store: Arc::from(store),
+            deadline,
+        }
+    }
+
+    pub fn from_config(config: &AppConfig, upstreams: &Upstreams) -> Result<Self, AppError> {
+        Ok(Self::new(
+            config.chains.clone(),
+            upstreams.rpc.clone(),
+            open_store(config, NAMESPACE_TOKENS)?,
+            Duration::from_secs(config.timeouts.token_metadata_secs),
+        ))
+    }
+
+    // None si la cadena no tiene rpc_url o el contrato no responde a ninguna
+    // de las funciones de metadatos. Un fallo del nodo o el plazo agotado son
+    // `RpcUnavailable` y no se cachean.
+    pub async fn resolve(
+        &self,
+        chain_id: u64,
+        address: &Address,
+    ) -> Result<Option<TokenMetadata>, AppError> {
+        let key = format!("{}/{}", chain_id, checksum_address(address));
+        if let Some(cached) = self.cached(&key).await {
+            debug!(token = ?address, "metadatos de token servidos desde la caché");
+            return Ok(cached);
+        }
+        let Some(provider) = self.providers.get(&chain_id) else {
+            return Ok(None);
+        };
+
+        let probes = async {
+            tokio::join!(
+                self.call(provider, address, "decimals"),
+                self.call(provider, address, "symbol"),
+                self.call(provider, address, "name"),
+                self.supports(provider, address, ERC721_INTERFACE),
+                self.supports(provider, address, ERC1155_INTERFACE),
+            )
+        };
+        let (decimals, symbol, name, erc721, erc1155) = tokio::time::timeout(self.deadline, probes)
+            .await
+            .map_err(|_| {
+                AppError::RpcUnavailable(format!(
+                    "metadatos de {:?} sin respuesta en {} s",
+                    address,
+                    self.deadline.as_secs()
+                ))
+            })?;
+        let decimals = decimals?.and_then(|data| decode_u8(&data));
+        let symbol = symbol?.and_then(|data| decode_text(&data));
+        let name = name?.and_then(|data| decode_text(&data));
+        let (erc721, erc1155) = (erc721?, erc1155?);
+
+        // Solo los ERC-20 tienen decimales; las colecciones se identifican
+        // por ERC-165 para no confundir un id de NFT con un importe
+        let standard = if decimals.is_some() {
+            Some(TokenStandard::Erc20)
+        } else if erc721 {
+            Some(TokenStandard::Erc721)
+        } else if erc1155 {
+            Some(TokenStandard::Erc1155)
+        } else {
+            None
+        };
+        debug!(
+            token = ?address,
+            standard = ?standard,
+            symbol = symbol.as_deref(),
+            decimals,
+            "metadatos de token leídos"
+        );
+
+        let metadata = Some(TokenMetadata {
+            standard,
+            name,
+            symbol,
+            decimals,
+        })
+        .filter(|token| token.standard.is_some() || token.symbol.is_some() || token.name.is_some());
+        self.save(key, metadata.as_ref()).await;
+        Ok(metadata)
+    }
+
+    // Some(None) si ya se comprobó que el contrato no es un token
+    async fn cached(&self, key: &str) -> Option<Option<TokenMetadata>> {
+        let (store, key) = (self.store.clone(), key.to_string());
+        match blocking(move || store.get(&key)).await {
+            Ok(value) => serde_json::from_str(&value?).ok(),
+            Err(e) => {
+                warn!(code = e.code(), error = %e, "no se pudo leer la caché de tokens");
+                None
+            }
+        }
+    }
+
+    async fn save(&self, key: String, metadata: Option<&TokenMetadata>) {
+        let store = self.store.clone();
+        let result = match serde_json::to_string(&metadata) {
+            Ok(value) => blocking(move || store.put(&key, &value)).await,
+            Err(e) => Err(AppError::Internal(e.to_string())),
+        };
+        if let Err(e) = result {
+            warn!(code = e.code(), error = %e, "no se pudieron guardar los metadatos del token");
+        }
+    }
+
+    // supportsInterface(bytes4) de ERC-165; false si el contrato no lo implementa
+    async fn supports(
+        &self,
+        provider: &Provider<Http>,
+        address: &Address,
+        interface: [u8; 4],
+    ) -> Result<bool, AppError> {
+        let mut data =
+            ethabi::short_signature("supportsInterface", &[ParamType::FixedBytes(4)]).to_vec();
+        data.extend(ethabi::encode(&[Token::FixedBytes(interface.to_vec())]));
+        let result = eth_call(&self.upstream, provider, address, &data).await?;
+        Ok(result
+            .and_then(|data| ethabi::decode(&[ParamType::Bool], &data).ok())
+            .is_some_and(|tokens| tokens == [Token::Bool(true)]))
+    }
+
+    // Llamada a una función sin argumentos
+    async fn call(
+        &self,
+        provider: &Provider<Http>,
+        address: &Address,
+        function: &str,
+    ) -> Result<Option<Vec<u8>>, AppError> {
+        let selector = ethabi::short_signature(function, &[]);
+        eth_call(&self.upstream, provider, address, &selector).await
+    }
+}
+
+// `string` según el estándar; algunos tokens antiguos (MKR, SAI) devuelven bytes32
+fn decode_text(data: &[u8]) -> Option<String> {
+    let text = match ethabi::decode(&[ParamType::String], data) {
+        Ok(tokens) => match tokens.as_slice() {
+            [Token::String(text)] => text.clone(),
+            _ => return None,
+        },
+        Err(_) if data.len() == 32 => {
+            let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+            String::from_utf8(data[..end].to_vec()).ok()?
+        }
+        Err(_) => return None,
+    };
+    let text = text.trim().to_string();
+    Some(text).filter(|text| !text.is_empty())
+}
+
+fn decode_u8(data: &[u8]) -> Option<u8> {
+    match ethabi::decode(&[ParamType::Uint(256)], data)
+        .ok()?
+        .as_slice()
+    {
+        [Token::Uint(value)] if value.bits() <= 8 => Some(value.low_u32() as u8),
+        _ => None,
+    }
+}
@@ -0,0 +1,195 @@
+use reqwest::{Client, IntoUrl, Method, RequestBuilder};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::error::AppError;
+use crate::request_id::propagate;
+use crate::{AppConfig, RetryPolicy};
+
+// Servicios externos a los que llama el servidor. Comparten un único
+// `reqwest::Client` (y su pool de conexiones); cada uno tiene su timeout,
+// política de reintentos y circuit breaker.
+pub struct Upstreams {
+    pub explorer: Arc<Upstream>,
+    pub llm: Arc<Upstream>,
+    pub rpc: Arc<Upstream>, // Nodos JSON-RPC de las cadenas (eth_call)
+}
+
+impl Upstreams {
+    pub fn from_config(config: &AppConfig) -> Result<Self, AppError> {
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(config.timeouts.connect_secs))
+            .build()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        Ok(Upstreams {
+            explorer: Arc::new(Upstream::new(
+                "explorer",
+                client.clone(),
+                Duration::from_secs(config.timeouts.explorer_secs),
+                config.retry.explorer.clone(),
+                AppError::ExplorerUnavailable,
+            )),
+            llm: Arc::new(Upstream::new(
+                "llm",
+                client.clone(),
+                Duration::from_secs(config.timeouts.llm_secs),
+                config.retry.llm.clone(),
+                AppError::LlmUnavailable,
+            )),
+            rpc: Arc::new(Upstream::new(
+                "rpc",
+                client,
+                Duration::from_secs(config.timeouts.rpc_secs),
+                config.retry.rpc.clone(),
+                AppError::RpcUnavailable,
+            )),
+        })
+    }
+}
+
+pub struct Upstream {
+    name: &'static str,
+    client: Client,
+    timeout: Duration,
+    policy: RetryPolicy,
+    unavailable: fn(String) -> AppError, // Error con el que se rechaza si el circuito está abierto
+    breaker: Mutex<BreakerState>,
+}
+
+// Circuit breaker: tras `breaker_threshold` fallos seguidos el circuito se
+// abre y las llamadas fallan sin salir del proceso; pasado el cooldown se deja
+// pasar una llamada de prueba que lo cierra o lo vuelve a abrir.
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probe_started: Option<Instant>,
+}
+
+impl Upstream {
+    pub fn new(
+        name: &'static str,
+        client: Client,
+        timeout: Duration,
+        policy: RetryPolicy,
+        unavailable: fn(String) -> AppError,
+    ) -> Self {
+        Upstream {
+            name,
+            client,
+            timeout,
+            policy,
+            unavailable,
+            breaker: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    // Petición con el timeout del servicio y el id de correlación en curso
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        propagate(self.client.request(method, url).timeout(self.timeout))
+    }
+
+    // Timeout de cada llamada, para clientes que no usan `request`
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    // Ejecuta `call` reintentando los fallos temporales (`AppError::is_transient`)
+    // con backoff exponencial. `call` debe construir la petición de nuevo en
+    // cada intento.
+    pub async fn execute<T, F, Fut>(&self, mut call: F) -> Result<T, AppError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        self.admit()?;
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(e) if e.is_transient() && attempt < self.policy.max_attempts => {
+                    let delay = self.backoff(attempt);
+                    warn!(
+                        upstream = self.name,
+                        attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        code = e.code(),
+                        error = %e,
+                        "fallo temporal, reintentando"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => {
+                    self.record(result.as_ref().err().is_some_and(AppError::is_transient));
+                    return result;
+                }
+            }
+        }
+    }
+
+    fn admit(&self) -> Result<(), AppError> {
+        let mut state = self
+            .breaker
+            .lock()
+            .map_err(|_| AppError::Internal("Lock del circuit breaker envenenado".to_string()))?;
+        let Some(open_until) = state.open_until else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(self.policy.breaker_cooldown_secs);
+        // Una sola llamada de prueba a la vez; si se abandonó, se permite otra
+        // pasado otro cooldown.
+        let probing = state
+            .probe_started
+            .is_some_and(|started| now.duration_since(started) < cooldown);
+        if now < open_until || probing {
+            let wait = open_until.saturating_duration_since(now).as_secs().max(1);
+            return Err((self.unavailable)(format!(
+                "circuito abierto tras {} fallos seguidos; reintenta en {} s",
+                state.consecutive_failures, wait
+            )));
+        }
+        state.probe_started = Some(now);
+        Ok(())
+    }
+
+    fn record(&self, failed: bool) {
+        let Ok(mut state) = self.breaker.lock() else {
+            return;
+        };
+        if !failed {
+            *state = BreakerState::default();
+            return;
+        }
+        state.consecutive_failures += 1;
+        if state.probe_started.is_some()
+            || state.consecutive_failures >= self.policy.breaker_threshold
+        {
+            state.open_until =
+                Some(Instant::now() + Duration::from_secs(self.policy.breaker_cooldown_secs));
+            state.probe_started = None;
+            warn!(
+                upstream = self.name,
+                failures = state.consecutive_failures,
+                cooldown_secs = self.policy.breaker_cooldown_secs,
+                "circuito abierto"
+            );
+        }
+    }
+
+    // initial_backoff_ms * 2^(intento-1), limitado a max_backoff_ms, con
+    // jitter en la mitad superior para no sincronizar a los workers
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = self
+            .policy
+            .initial_backoff_ms
+            .saturating_mul(1u64 << (attempt - 1).min(16))
+            .min(self.policy.max_backoff_ms);
+        let jitter = RandomState::new().build_hasher().finish() % (base / 2 + 1);
+        Duration::from_millis(base / 2 + jitter)
+    }
+}
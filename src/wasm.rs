@@ -0,0 +1,70 @@
+use ethabi::ethereum_types::H256;
+use serde::Serialize;
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::decode;
+use crate::decoder::load_contract;
+use crate::error::AppError;
+use crate::human_abi::parse_abi_str;
+
+// Bindings para JavaScript (extensión del navegador y frontend). El host
+// obtiene el ABI por su cuenta y lo pasa como texto: un array ABI, un
+// artefacto de compilación con campo "abi", firmas legibles o una interfaz
+// de Solidity (ver `human_abi`).
+
+#[wasm_bindgen(js_name = decodeCall)]
+pub fn decode_call(abi_json: &str, call_data: &str) -> Result<JsValue, JsError> {
+    let contract = parse_contract(abi_json)?;
+    let (function, tokens) = decode::decode_function(&contract, call_data).map_err(js_error)?;
+    to_js(&decode::describe_call(function, &tokens))
+}
+
+#[wasm_bindgen]
+pub fn selector(signature: &str) -> Result<String, JsError> {
+    decode::selector(signature).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = decodeLog)]
+pub fn decode_log(abi_json: &str, topics: Vec<String>, data: &str) -> Result<JsValue, JsError> {
+    let contract = parse_contract(abi_json)?;
+    let topics = topics
+        .iter()
+        .map(|topic| {
+            topic
+                .parse::<H256>()
+                .map_err(|e| js_error(AppError::InvalidHex(format!("{}: {}", topic, e))))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let log = decode::decode_log(&contract, &topics, data).map_err(js_error)?;
+
+    let params: Vec<Value> = log
+        .params
+        .iter()
+        .map(|param| json!({"name": param.name, "value": decode::token_to_json(&param.value)}))
+        .collect();
+    to_js(&json!({ "params": params }))
+}
+
+#[wasm_bindgen(js_name = decodeError)]
+pub fn decode_error(abi_json: &str, revert_data: &str) -> Result<JsValue, JsError> {
+    let contract = parse_contract(abi_json)?;
+    to_js(&decode::decode_error(&contract, revert_data).map_err(js_error)?)
+}
+
+fn parse_contract(abi_json: &str) -> Result<ethabi::Contract, JsError> {
+    let abi = parse_abi_str(abi_json).map_err(js_error)?;
+    load_contract(&abi).map_err(js_error)
+}
+
+// Objetos JS planos (no Map) para que el resultado se use como JSON normal
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+// El mensaje lleva el código estable del error, igual que la salida del CLI
+fn js_error(e: AppError) -> JsError {
+    JsError::new(&format!("[{}] {}", e.code(), e))
+}
@@ -0,0 +1,72 @@
+// Revalidación de ABIs caducados: un fallo del proveedor no se repite en cada
+// petición y las entradas sin fecha no se dan por caducadas.
+#![cfg(feature = "explorer")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ethabi::Address;
+use rust_decompile_test::abi::MemoryAbiCache;
+use rust_decompile_test::decoder::{CachedAbi, FetchedAbi};
+use rust_decompile_test::error::AppError;
+use rust_decompile_test::{AbiCache, AbiProvider, Decoder};
+use serde_json::json;
+
+const CHAIN_ID: u64 = 421614;
+
+// Explorador caído que cuenta las consultas recibidas
+struct DownProvider(Arc<AtomicUsize>);
+
+#[async_trait]
+impl AbiProvider for DownProvider {
+    async fn fetch_abi(&self, _chain_id: u64, _address: &Address) -> Result<FetchedAbi, AppError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Err(AppError::ExplorerUnavailable("sin conexión".to_string()))
+    }
+
+    fn name(&self) -> &str {
+        "explorer"
+    }
+}
+
+fn decoder(fetched_at: Option<u64>) -> (Decoder, Arc<AtomicUsize>) {
+    let cache = MemoryAbiCache::new();
+    let fetched = FetchedAbi {
+        abi: json!([{"type": "function", "name": "f", "inputs": [], "outputs": []}]),
+        ..FetchedAbi::default()
+    };
+    let mut entry = CachedAbi::new(CHAIN_ID, Address::repeat_byte(0xaa), "explorer", fetched);
+    entry.fetched_at = fetched_at;
+    cache.put(&entry).unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let decoder = Decoder::new(cache)
+        .with_provider(DownProvider(calls.clone()))
+        .with_ttl(Some(Duration::from_secs(60)));
+    (decoder, calls)
+}
+
+#[tokio::test]
+async fn failed_revalidation_is_not_retried_on_every_request() {
+    let (decoder, calls) = decoder(Some(1));
+    let address = Address::repeat_byte(0xaa);
+    for _ in 0..3 {
+        let (contract, _) = decoder.get_abi(CHAIN_ID, &address).await.unwrap();
+        assert!(contract.function("f").is_ok());
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn undated_entries_are_stamped_instead_of_revalidated() {
+    let (decoder, calls) = decoder(None);
+    let address = Address::repeat_byte(0xaa);
+    decoder.get_abi(CHAIN_ID, &address).await.unwrap();
+    decoder.get_abi(CHAIN_ID, &address).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    let stamped = decoder.cached_abi(CHAIN_ID, &address).unwrap().unwrap();
+    assert!(stamped.fetched_at.is_some());
+}
@@ -0,0 +1,69 @@
+// Caducidad de la caché de análisis: las entradas caducadas no se sirven y se
+// borran del almacén, al leerlas o al limpiar por antigüedad.
+#![cfg(feature = "server")]
+
+use std::time::Duration;
+
+use rust_decompile_test::llm::{AnalysisCache, LlmPrompt, RiskVerdict};
+use rust_decompile_test::store::{CacheStore, FsStore, SqliteStore};
+use serde_json::json;
+
+fn prompt(hash: &str) -> LlmPrompt {
+    LlmPrompt {
+        model: "gpt-4o-mini".to_string(),
+        prompt_hash: hash.to_string(),
+        body: json!({}),
+    }
+}
+
+fn verdict() -> RiskVerdict {
+    RiskVerdict {
+        risk_level: Some("low".to_string()),
+        explanation: Some("transferencia estándar".to_string()),
+        prompt_tokens: None,
+        completion_tokens: None,
+    }
+}
+
+#[tokio::test]
+async fn expired_entries_are_deleted_when_read() {
+    let dir = std::env::temp_dir().join(format!("rdt-analysis-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = AnalysisCache::new(Box::new(FsStore::new(&dir)), Some(Duration::from_secs(1)));
+
+    cache.put(&prompt("a1"), &verdict()).await;
+    let cached = cache.get(&prompt("a1")).await.unwrap();
+    assert_eq!(cached.risk_level.as_deref(), Some("low"));
+    assert_eq!(FsStore::new(&dir).keys().unwrap().len(), 1);
+
+    std::thread::sleep(Duration::from_millis(1100));
+    assert!(cache.get(&prompt("a1")).await.is_none());
+    assert!(FsStore::new(&dir).keys().unwrap().is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn writing_purges_entries_left_by_previous_runs() {
+    let path = std::env::temp_dir().join(format!("rdt-analysis-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    SqliteStore::open(&path, "analysis")
+        .unwrap()
+        .put("antiguo", "{}")
+        .unwrap();
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute("UPDATE cache SET updated_at = updated_at - 7200", [])
+        .unwrap();
+
+    let store = SqliteStore::open(&path, "analysis").unwrap();
+    let cache = AnalysisCache::new(Box::new(store), Some(Duration::from_secs(3600)));
+    cache.put(&prompt("b2"), &verdict()).await;
+
+    let keys = SqliteStore::open(&path, "analysis")
+        .unwrap()
+        .keys()
+        .unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_ne!(keys[0], "antiguo");
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,196 @@
+// Mismo contrato para todos los backends de `CacheStore`. El backend Redis se
+// prueba contra un servidor RESP mínimo en proceso.
+#![cfg(feature = "server")]
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_decompile_test::store::{CacheStore, FsStore, RedisStore, SqliteStore};
+
+fn exercise(store: &dyn CacheStore) {
+    assert_eq!(store.get("421614/0xAbC").unwrap(), None);
+    assert!(store.keys().unwrap().is_empty());
+
+    store.put("421614/0xAbC", r#"{"v":1}"#).unwrap();
+    store.put("2e64cec1", "[]").unwrap();
+    store.put("421614/0xAbC", r#"{"v":2}"#).unwrap();
+    assert_eq!(
+        store.get("421614/0xAbC").unwrap().as_deref(),
+        Some(r#"{"v":2}"#)
+    );
+
+    let mut keys = store.keys().unwrap();
+    keys.sort();
+    assert_eq!(keys, vec!["2e64cec1", "421614/0xAbC"]);
+
+    assert!(store.remove("2e64cec1").unwrap());
+    assert!(!store.remove("2e64cec1").unwrap());
+    assert_eq!(store.keys().unwrap(), vec!["421614/0xAbC"]);
+}
+
+#[test]
+fn fs_store() {
+    let dir = std::env::temp_dir().join(format!("rdt-cache-store-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    exercise(&FsStore::new(&dir));
+    assert!(FsStore::new(&dir).get("../fuera").is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sqlite_store() {
+    exercise(&SqliteStore::open_in_memory("abis").unwrap());
+}
+
+// Las entradas que caducan se borran por antigüedad (segundos enteros en
+// SQLite, fecha de modificación en disco); Redis las caduca él mismo con `EX`
+#[test]
+fn expired_entries_are_purged() {
+    let dir = std::env::temp_dir().join(format!("rdt-cache-purge-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let fs = FsStore::new(&dir);
+    let sqlite = SqliteStore::open_in_memory("analysis").unwrap();
+    let redis = RedisStore::open(
+        &spawn_resp_server(),
+        "rdt:analysis:",
+        Duration::from_secs(2),
+    )
+    .unwrap();
+    let stores: [&dyn CacheStore; 3] = [&fs, &sqlite, &redis];
+    for store in stores {
+        store
+            .put_expiring("viejo", "{}", Duration::from_secs(1))
+            .unwrap();
+        assert!(store.get("viejo").unwrap().is_some());
+    }
+    thread::sleep(Duration::from_millis(2100));
+    assert_eq!(redis.get("viejo").unwrap(), None);
+
+    for store in [&fs as &dyn CacheStore, &sqlite] {
+        store
+            .put_expiring("nuevo", "{}", Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(store.purge_older_than(Duration::from_secs(1)).unwrap(), 1);
+        assert_eq!(store.keys().unwrap(), vec!["nuevo"]);
+        assert_eq!(
+            store.purge_older_than(Duration::from_secs(3600)).unwrap(),
+            0
+        );
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn redis_store() {
+    let url = spawn_resp_server();
+    let store = RedisStore::open(&url, "rdt:abis:", Duration::from_secs(2)).unwrap();
+    exercise(&store);
+    // Los espacios de nombres no se ven entre sí
+    let other = RedisStore::open(&url, "rdt:selectors:", Duration::from_secs(2)).unwrap();
+    assert!(other.keys().unwrap().is_empty());
+}
+
+#[test]
+fn redis_store_is_shared_between_threads() {
+    let url = spawn_resp_server();
+    let store = Arc::new(RedisStore::open(&url, "rdt:tokens:", Duration::from_secs(2)).unwrap());
+    let workers: Vec<_> = (0..8)
+        .map(|worker| {
+            let store = store.clone();
+            thread::spawn(move || {
+                for i in 0..20 {
+                    let key = format!("{}/{}", worker, i);
+                    store.put(&key, "null").unwrap();
+                    assert_eq!(store.get(&key).unwrap().as_deref(), Some("null"));
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert_eq!(store.keys().unwrap().len(), 160);
+}
+
+// Valor y caducidad (`SET … EX`) de cada clave
+type RespData = Arc<Mutex<BTreeMap<String, (String, Option<Instant>)>>>;
+
+// Servidor con el subconjunto del protocolo de Redis que usa `RedisStore`
+// (GET, SET con EX opcional, DEL, SCAN); el resto de comandos responde +OK.
+fn spawn_resp_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("redis://{}", listener.local_addr().unwrap());
+    let data = RespData::default();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let data = data.clone();
+            thread::spawn(move || serve_connection(stream, data));
+        }
+    });
+    url
+}
+
+fn serve_connection(stream: TcpStream, data: RespData) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    while let Some(command) = read_command(&mut reader) {
+        let mut data = data.lock().unwrap();
+        let now = Instant::now();
+        data.retain(|_, (_, expires)| expires.is_none_or(|expires| expires > now));
+        let reply = match command[0].to_uppercase().as_str() {
+            "GET" => match data.get(&command[1]) {
+                Some((value, _)) => bulk(value),
+                None => "$-1\r\n".to_string(),
+            },
+            "SET" => {
+                let expires = match command.get(3).map(|option| option.to_uppercase()) {
+                    Some(option) if option == "EX" => {
+                        Some(now + Duration::from_secs(command[4].parse().unwrap()))
+                    }
+                    _ => None,
+                };
+                data.insert(command[1].clone(), (command[2].clone(), expires));
+                "+OK\r\n".to_string()
+            }
+            "DEL" => format!(":{}\r\n", u8::from(data.remove(&command[1]).is_some())),
+            "SCAN" => {
+                let prefix = command[3].trim_end_matches('*');
+                let keys: Vec<&String> = data.keys().filter(|k| k.starts_with(prefix)).collect();
+                let mut reply = format!("*2\r\n{}*{}\r\n", bulk("0"), keys.len());
+                for key in keys {
+                    reply.push_str(&bulk(key));
+                }
+                reply
+            }
+            _ => "+OK\r\n".to_string(),
+        };
+        if writer.write_all(reply.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn read_command(reader: &mut impl BufRead) -> Option<Vec<String>> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok().filter(|read| *read > 0)?;
+    let count: usize = line.trim().strip_prefix('*')?.parse().ok()?;
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        let len: usize = line.trim().strip_prefix('$')?.parse().ok()?;
+        let mut buf = vec![0; len + 2];
+        reader.read_exact(&mut buf).ok()?;
+        buf.truncate(len);
+        args.push(String::from_utf8(buf).ok()?);
+    }
+    Some(args)
+}
+
+fn bulk(value: &str) -> String {
+    format!("${}\r\n{}\r\n", value.len(), value)
+}
@@ -0,0 +1,108 @@
+// /encode es el inverso de /decode: la call data construida a partir de
+// argumentos JSON debe decodificarse a los mismos tokens.
+use ethabi::{Contract, Function};
+use rust_decompile_test::decode::{decode_function_call, token_to_json};
+use rust_decompile_test::decoder::load_contract;
+use rust_decompile_test::encode::{encode_function_call, find_function, json_to_tokens};
+use rust_decompile_test::human_abi::parse_abi_str;
+use serde_json::{json, Value};
+
+const ABI: &str = "
+    struct Order { address maker; uint256[] amounts; int8 side; }
+    function fill(Order order, bytes data, bytes32 salt, bool partial) returns (uint256)
+    function transfer(address to, uint256 amount)
+    function transfer(address to, uint256 amount, bytes memo)
+";
+
+fn contract() -> Contract {
+    load_contract(&parse_abi_str(ABI).unwrap()).unwrap()
+}
+
+fn round_trip(contract: &Contract, function: &str, arguments: Value) -> (String, Vec<Value>) {
+    let function = find_function(contract, function).unwrap();
+    let tokens = json_to_tokens(function, &arguments).unwrap();
+    let call_data = encode_function_call(function, &tokens).unwrap();
+
+    let (name, decoded) =
+        decode_function_call(contract, &format!("0x{}", hex::encode(call_data))).unwrap();
+    assert_eq!(decoded, tokens);
+    (name, decoded.iter().map(token_to_json).collect())
+}
+
+#[test]
+fn nested_arguments_round_trip() {
+    let maker = "0x00000000000000000000000000000000000000aa";
+    let salt = format!("0x{}", "11".repeat(32));
+    let (name, decoded) = round_trip(
+        &contract(),
+        "fill",
+        json!([[maker, [1, "2", "0xff"], -3], "0xdeadbeef", salt, true]),
+    );
+
+    assert_eq!(name, "fill");
+    assert_eq!(
+        decoded,
+        [
+            json!([maker, ["1", "2", "255"], "-3"]),
+            json!("0xdeadbeef"),
+            json!(salt),
+            json!(true),
+        ]
+    );
+}
+
+#[test]
+fn overloads_need_a_signature_and_accept_named_arguments() {
+    let contract = contract();
+    let err = find_function(&contract, "transfer").unwrap_err();
+    assert_eq!(err.code(), "INVALID_REQUEST");
+    assert!(err.to_string().contains("transfer(address,uint256,bytes)"));
+
+    let (name, decoded) = round_trip(
+        &contract,
+        "transfer(address to, uint amount, bytes memo)",
+        json!({ "to": "0x0000000000000000000000000000000000000001", "amount": "1000", "memo": "0x" }),
+    );
+    assert_eq!(name, "transfer");
+    assert_eq!(decoded[1], json!("1000"));
+}
+
+#[test]
+fn rejects_values_outside_the_type() {
+    let contract = contract();
+    let fill = find_function(&contract, "fill").unwrap();
+    let maker = "0x00000000000000000000000000000000000000aa";
+
+    let err = json_to_tokens(fill, &json!([[maker, [], 128], "0x", "0x00", false])).unwrap_err();
+    assert_eq!(err.code(), "INVALID_ARGUMENTS");
+    assert!(err.to_string().contains("order.2"));
+
+    let err = json_to_tokens(fill, &json!([[maker, [], 0], "0x", "0x00", false])).unwrap_err();
+    assert!(err.to_string().contains("salt"));
+
+    let err = find_function(&contract, "approve").unwrap_err();
+    assert_eq!(err.code(), "FUNCTION_NOT_FOUND");
+}
+
+// Un `Function` que no viene de `load_contract` puede traer anchos que ethabi
+// acepta al leer el tipo; se rechazan en lugar de desbordar o truncar
+#[test]
+fn rejects_impossible_type_widths() {
+    for (kind, value) in [
+        ("int0", json!(0)),
+        ("uint512", json!(1)),
+        ("bytes33", json!("0x00")),
+    ] {
+        let function: Function = serde_json::from_value(json!({
+            "type": "function",
+            "name": "f",
+            "inputs": [{"name": "x", "type": kind}],
+            "outputs": [],
+            "stateMutability": "nonpayable",
+        }))
+        .unwrap();
+        let err = json_to_tokens(&function, &json!([value])).unwrap_err();
+        assert_eq!(err.code(), "INVALID_ARGUMENTS");
+        assert!(err.to_string().contains(kind), "{}", err);
+    }
+}
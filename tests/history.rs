@@ -0,0 +1,278 @@
+// Filtros y paginación del historial contra una base de datos SQLite temporal,
+// directamente y a través de GET /history.
+#![cfg(feature = "server")]
+
+use actix_web::http::StatusCode;
+use actix_web::test::{call_and_read_body_json, call_service, init_service, TestRequest};
+use actix_web::{web, App};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+use rust_decompile_test::abi::MemoryAbiCache;
+use rust_decompile_test::handlers;
+use rust_decompile_test::history::{page_bounds, HistoryStore, NewHistoryEntry};
+use rust_decompile_test::{AppConfig, Decoder, HistoryQuery};
+
+const TOKEN: &str = "0xDDC30F0BFAEE96BC655BF7A815193061999DEDBB";
+const VAULT: &str = "0x742d35cc6634c0532925a3b844bc454e4438f44e";
+
+// Base de datos en un archivo propio de cada test; se borra al terminar
+struct TempDb(PathBuf);
+
+impl TempDb {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("rdt-history-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        TempDb(path)
+    }
+
+    fn open(&self) -> HistoryStore {
+        HistoryStore::open(&self.0).unwrap()
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn entry(contract: &str, function: &str, risk_level: Option<&str>) -> NewHistoryEntry {
+    let endpoint = if risk_level.is_some() {
+        "analysis"
+    } else {
+        "decode"
+    };
+    NewHistoryEntry {
+        endpoint: endpoint.to_string(),
+        contract_address: contract.to_string(),
+        call_data: "0xa9059cbb".to_string(),
+        request: json!({}),
+        http_status: 200,
+        status: "success".to_string(),
+        function_name: Some(function.to_string()),
+        risk_level: risk_level.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+// Cinco registros: tres del token (transfer, approve, transfer) y dos del vault
+fn seed(store: &HistoryStore) -> Vec<i64> {
+    [
+        entry(TOKEN, "transfer", None),
+        entry(TOKEN, "approve", Some("high")),
+        entry(VAULT, "deposit", Some("low")),
+        entry(TOKEN, "transfer", Some("LOW")),
+        entry(VAULT, "withdraw", None),
+    ]
+    .iter()
+    .map(|entry| store.record(entry).unwrap())
+    .collect()
+}
+
+fn ids(store: &HistoryStore, query: &HistoryQuery) -> (Vec<i64>, u64) {
+    let (items, total) = store.query(query).unwrap();
+    (items.iter().map(|item| item.id).collect(), total)
+}
+
+#[test]
+fn filters_combine_and_ignore_case() {
+    let db = TempDb::new("filters");
+    let store = db.open();
+    let id = seed(&store);
+
+    // La dirección se guarda y se compara en minúsculas
+    let by_contract = HistoryQuery {
+        contract: Some(TOKEN.to_lowercase()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &by_contract), (vec![id[3], id[1], id[0]], 3));
+
+    let transfers = HistoryQuery {
+        contract: Some(TOKEN.to_string()),
+        function: Some("transfer".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &transfers), (vec![id[3], id[0]], 2));
+
+    let low_risk = HistoryQuery {
+        risk_level: Some("low".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &low_risk), (vec![id[3], id[2]], 2));
+
+    let unknown = HistoryQuery {
+        function: Some("mint".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &unknown), (vec![], 0));
+}
+
+#[test]
+fn date_range_is_inclusive_and_validated() {
+    let db = TempDb::new("dates");
+    let store = db.open();
+    let id = seed(&store);
+
+    let created_at = store.get(id[2]).unwrap().unwrap().created_at;
+    let exact = HistoryQuery {
+        from: Some(created_at.clone()),
+        to: Some(created_at),
+        ..Default::default()
+    };
+    assert!(ids(&store, &exact).0.contains(&id[2]));
+
+    // Otras zonas horarias se normalizan a UTC antes de comparar
+    let all = HistoryQuery {
+        from: Some("2000-01-01T02:00:00+02:00".to_string()),
+        to: Some("2999-12-31T23:59:59Z".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &all).1, 5);
+
+    let future = HistoryQuery {
+        from: Some("2999-01-01T00:00:00Z".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &future), (vec![], 0));
+
+    let invalid = HistoryQuery {
+        to: Some("ayer".to_string()),
+        ..Default::default()
+    };
+    let Err(error) = store.query(&invalid) else {
+        panic!("la fecha inválida debería rechazarse");
+    };
+    assert_eq!(error.code(), "INVALID_REQUEST");
+}
+
+#[test]
+fn pages_are_newest_first_with_the_filtered_total() {
+    let db = TempDb::new("pages");
+    let store = db.open();
+    let id = seed(&store);
+
+    let page = |page, per_page| HistoryQuery {
+        page: Some(page),
+        per_page: Some(per_page),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &page(1, 2)), (vec![id[4], id[3]], 5));
+    assert_eq!(ids(&store, &page(2, 2)), (vec![id[2], id[1]], 5));
+    assert_eq!(ids(&store, &page(3, 2)), (vec![id[0]], 5));
+    assert_eq!(ids(&store, &page(4, 2)), (vec![], 5));
+
+    let filtered = HistoryQuery {
+        contract: Some(VAULT.to_string()),
+        per_page: Some(1),
+        page: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(ids(&store, &filtered), (vec![id[2]], 2));
+}
+
+#[test]
+fn page_bounds_are_clamped() {
+    let bounds = |page, per_page| {
+        page_bounds(&HistoryQuery {
+            page,
+            per_page,
+            ..Default::default()
+        })
+    };
+    assert_eq!(bounds(None, None), (1, 50));
+    assert_eq!(bounds(Some(0), Some(0)), (1, 1));
+    assert_eq!(bounds(Some(3), Some(10_000)), (3, 500));
+}
+
+#[test]
+fn entries_survive_reopening_the_database() {
+    let db = TempDb::new("reopen");
+    let id = seed(&db.open());
+    let store = db.open();
+    assert_eq!(ids(&store, &HistoryQuery::default()).1, 5);
+    let entry = store.get(id[1]).unwrap().unwrap();
+    assert_eq!(entry.function_name.as_deref(), Some("approve"));
+    assert_eq!(entry.contract_address, TOKEN.to_lowercase());
+    assert!(store.get(id[4] + 1).unwrap().is_none());
+}
+
+#[actix_web::test]
+async fn history_endpoints_read_the_database() {
+    let db = TempDb::new("http");
+    let store = db.open();
+    let id = seed(&store);
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(store))
+            .configure(handlers::configure),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri(&format!(
+            "/history?contract={}&risk_level=LOW&per_page=1",
+            TOKEN
+        ))
+        .to_request();
+    let body: Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["page"], 1);
+    assert_eq!(body["per_page"], 1);
+    assert_eq!(body["items"][0]["id"], id[3]);
+
+    let req = TestRequest::get()
+        .uri("/history?from=no-es-una-fecha")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::get()
+        .uri(&format!("/history/{}", id[2]))
+        .to_request();
+    let body: Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(body["function_name"], "deposit");
+
+    let req = TestRequest::get()
+        .uri(&format!("/history/{}", id[4] + 1))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+// `save_abi` sin clave de administración se rechaza con 403 y el intento
+// queda registrado
+#[actix_web::test]
+async fn rejected_admin_attempts_are_recorded() {
+    let db = TempDb::new("rejected");
+    let mut config = AppConfig::default();
+    config.auth.enabled = true;
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(config))
+            .app_data(web::Data::new(Decoder::new(MemoryAbiCache::new())))
+            .app_data(web::Data::new(db.open()))
+            .configure(handlers::configure),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/decode")
+        .set_json(json!({
+            "contract_address": VAULT,
+            "call_data": "0xa9059cbb",
+            "signature": "transfer(address,uint256)",
+            "save_abi": true,
+        }))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let (items, total) = db.open().query(&HistoryQuery::default()).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(items[0].endpoint, "decode");
+    assert_eq!(items[0].http_status, 403);
+    assert_eq!(items[0].status, "error");
+    assert!(items[0].request["save_abi"].as_bool().unwrap());
+}
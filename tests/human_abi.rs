@@ -0,0 +1,95 @@
+// Firmas legibles e interfaces de Solidity deben dar el mismo ABI que el JSON
+// equivalente del compilador.
+use rust_decompile_test::decode::{canonical_signature, decode_function_call};
+use rust_decompile_test::decoder::load_contract;
+use rust_decompile_test::human_abi::{normalize_abi, parse_abi_str};
+use serde_json::{json, Value};
+
+const TRANSFER_CALL: &str = "0xa9059cbb\
+    0000000000000000000000000000000000000000000000000000000000000001\
+    0000000000000000000000000000000000000000000000000000000000000005";
+
+#[test]
+fn human_readable_signatures() {
+    let abi = normalize_abi(json!([
+        "function transfer(address to, uint amount) returns (bool)",
+        "event Transfer(address indexed from, address indexed to, uint256 value)",
+        "error InsufficientBalance(uint256 available, uint256 required)",
+    ]))
+    .unwrap();
+    let contract = load_contract(&abi).unwrap();
+
+    let (name, args) = decode_function_call(&contract, TRANSFER_CALL).unwrap();
+    assert_eq!(name, "transfer");
+    assert_eq!(args.len(), 2);
+    assert!(contract.event("Transfer").unwrap().inputs[0].indexed);
+    assert_eq!(contract.errors().count(), 1);
+    assert_eq!(abi[0]["inputs"][1]["type"], "uint256");
+}
+
+#[test]
+fn solidity_interface_with_structs() {
+    let source = r#"
+        // SPDX-License-Identifier: MIT
+        pragma solidity ^0.8.20;
+
+        interface IPool {
+            enum Side { Buy, Sell }
+            struct Order { address maker; uint256[] amounts; Side side; }
+
+            function fill(Order calldata order, IPool pool) external payable returns (uint256);
+            function pending() external view returns (Order[] memory);
+        }
+
+        contract Pool is IPool {
+            modifier onlyOwner() { _; }
+            function pause() external onlyOwner { }
+            function _fee(uint x) internal pure returns (uint) { return x; }
+        }
+    "#;
+    let contract = load_contract(&parse_abi_str(source).unwrap()).unwrap();
+
+    let fill = contract.function("fill").unwrap();
+    let kinds: Vec<_> = fill.inputs.iter().map(|p| p.kind.clone()).collect();
+    assert_eq!(
+        canonical_signature("fill", &kinds),
+        "fill((address,uint256[],uint8),address)"
+    );
+    assert!(contract.function("pause").is_ok());
+    assert!(contract.function("_fee").is_err());
+}
+
+#[test]
+fn rejects_unknown_types_and_typos() {
+    let err = parse_abi_str("function a(uint256 x)\nfunction b(addres to)").unwrap_err();
+    assert_eq!(err.code(), "INVALID_ABI");
+    assert!(err.to_string().contains("addres"));
+
+    let err = parse_abi_str("function a(uint256 x)\nfunctoin b()").unwrap_err();
+    assert!(err.to_string().contains("línea 2"));
+}
+
+// ethabi carga estos anchos desde un ABI JSON; deben rechazarse igual que en
+// las firmas legibles, también dentro de arrays, tuplas, eventos y errores
+#[test]
+fn json_abis_with_invalid_widths_are_rejected() {
+    let param = |kind: &str| json!({"name": "x", "type": kind});
+    let function =
+        |inputs: Value| json!([{"type": "function", "name": "f", "inputs": inputs, "outputs": []}]);
+    let tuple = json!({"name": "t", "type": "tuple[]", "components": [param("bytes33")]});
+    let abis = [
+        function(json!([param("uint512")])),
+        json!([{"type": "function", "name": "f", "inputs": [], "outputs": [param("int0[2]")]}]),
+        function(json!([tuple])),
+        json!([{"type": "event", "name": "E", "inputs": [param("int264")], "anonymous": false}]),
+        json!([{"type": "error", "name": "Oops", "inputs": [param("uint7")]}]),
+    ];
+    for abi in abis {
+        let err = load_contract(&normalize_abi(abi).unwrap()).unwrap_err();
+        assert_eq!(err.code(), "INVALID_ABI", "{}", err);
+        assert!(err.to_string().contains("tipo desconocido"));
+    }
+
+    let valid = function(json!([param("int8"), param("bytes32")]));
+    assert!(load_contract(&valid).is_ok());
+}
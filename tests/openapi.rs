@@ -0,0 +1,91 @@
+// Comprueba que la especificación OpenAPI y las rutas de `handlers::configure`
+// coinciden: cada operación documentada tiene ruta y cada endpoint registrado
+// está documentado.
+#![cfg(feature = "server")]
+
+use actix_web::http::{Method, StatusCode};
+use actix_web::{test, web, App, HttpResponse};
+use serde_json::Value;
+use utoipa::OpenApi;
+
+use rust_decompile_test::abi::MemoryAbiCache;
+use rust_decompile_test::handlers;
+use rust_decompile_test::history::HistoryStore;
+use rust_decompile_test::metrics::Metrics;
+use rust_decompile_test::openapi::ApiDoc;
+use rust_decompile_test::upstream::Upstreams;
+use rust_decompile_test::{AppConfig, Decoder};
+
+// Status que devuelve el servicio por defecto, i.e. ruta no registrada
+const UNROUTED: StatusCode = StatusCode::IM_A_TEAPOT;
+
+#[actix_web::test]
+async fn every_documented_operation_is_routed() {
+    let config = AppConfig::default();
+    let upstreams = Upstreams::from_config(&config).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(config))
+            .app_data(web::Data::new(Decoder::new(MemoryAbiCache::new())))
+            .app_data(web::Data::new(HistoryStore::open_in_memory().unwrap()))
+            .app_data(web::Data::new(upstreams))
+            .app_data(web::Data::new(Metrics::new()))
+            .configure(handlers::configure)
+            .default_service(web::to(|| async { HttpResponse::build(UNROUTED).finish() })),
+    )
+    .await;
+
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let paths = spec["paths"].as_object().unwrap();
+    assert!(!paths.is_empty());
+
+    for (path, operations) in paths {
+        // Los parámetros de ruta se sustituyen por un valor válido cualquiera
+        let uri = path
+            .replace("{id}", "1")
+            .replace("{chain_id}", "421614")
+            .replace("{address}", "0xddc30F0bFaEe96Bc655BF7a815193061999dEDBb");
+        for method in operations.as_object().unwrap().keys() {
+            let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+            let mut req = test::TestRequest::default()
+                .method(method.clone())
+                .uri(&uri);
+            if method == Method::POST {
+                req = req.set_json(Value::Object(Default::default()));
+            }
+            let resp = test::call_service(&app, req.to_request()).await;
+            assert_ne!(
+                resp.status(),
+                UNROUTED,
+                "{} {} está en la especificación pero no tiene ruta",
+                method,
+                path
+            );
+        }
+    }
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/openapi.json").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn every_routed_endpoint_is_documented() {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let routes = handlers::routes();
+    assert!(!routes.is_empty());
+
+    for (path, method) in routes {
+        let documented = spec["paths"][path]
+            .get(method.as_str().to_lowercase())
+            .is_some();
+        assert!(
+            documented,
+            "{} {} tiene ruta pero no está en la especificación",
+            method, path
+        );
+    }
+}
@@ -0,0 +1,188 @@
+// El modo estricto debe aceptar la codificación canónica y señalar cada
+// desviación que ethabi tolera al decodificar.
+use ethabi::{Function, ParamType, Token};
+use rust_decompile_test::decode::decode_function;
+use rust_decompile_test::decoder::load_contract;
+use rust_decompile_test::human_abi::parse_abi_str;
+use rust_decompile_test::strict::check_encoding;
+use rust_decompile_test::{EncodingFinding, FindingKind};
+use serde_json::json;
+
+fn word(value: u64) -> String {
+    format!("{:064x}", value)
+}
+
+fn findings(abi: &str, call_data: &str) -> Vec<EncodingFinding> {
+    let contract = load_contract(&parse_abi_str(abi).unwrap()).unwrap();
+    let (function, tokens) = decode_function(&contract, call_data).unwrap();
+    check_encoding(function, &tokens, call_data).unwrap()
+}
+
+#[test]
+fn canonical_call_has_no_findings() {
+    let call_data = format!("0xa9059cbb{}{}", word(1), word(5));
+    assert!(findings("function transfer(address to, uint256 amount)", &call_data).is_empty());
+}
+
+#[test]
+fn dirty_address_padding_and_trailing_bytes() {
+    let dirty_to = format!("{}{}", "ff".repeat(12), &word(1)[24..]);
+    let call_data = format!("0xa9059cbb{}{}deadbeef", dirty_to, word(5));
+    let found = findings("function transfer(address to, uint256 amount)", &call_data);
+
+    let kinds: Vec<_> = found.iter().map(|f| f.kind).collect();
+    assert_eq!(kinds, [FindingKind::DirtyPadding, FindingKind::ExtraBytes]);
+    assert_eq!(found[0].path.as_deref(), Some("to"));
+    assert_eq!(found[0].offset, 4);
+    assert_eq!(found[1].offset, 68);
+}
+
+#[test]
+fn non_canonical_offset_does_not_shift_content() {
+    // f(bytes,bool) con el contenido de `b` una palabra más lejos de lo
+    // canónico, `ok` = 2 y un byte sucio tras "abcdef"
+    let data = format!("abcdef{}11", "00".repeat(28));
+    let call_data = format!(
+        "0x91c0b7ed{}{}{}{}{}",
+        word(0x60),
+        word(2),
+        word(0),
+        word(3),
+        data
+    );
+    let found = findings("function f(bytes b, bool ok)", &call_data);
+
+    let kinds: Vec<_> = found.iter().map(|f| f.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            FindingKind::NonCanonicalOffset,
+            FindingKind::NonCanonicalValue,
+            FindingKind::DirtyPadding,
+        ]
+    );
+    assert_eq!(found[1].path.as_deref(), Some("ok"));
+    assert_eq!(found[2].offset, 4 + 4 * 32);
+}
+
+fn selector(name: &str, kind: ParamType) -> String {
+    hex::encode(ethabi::short_signature(name, &[kind]))
+}
+
+#[test]
+fn dirty_high_bits_in_small_integers() {
+    // uint8 con bits por encima del byte bajo
+    let call_data = format!(
+        "0x{}{}01",
+        selector("f", ParamType::Uint(8)),
+        "ff".repeat(31)
+    );
+    let found = findings("function f(uint8 x)", &call_data);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].kind, FindingKind::DirtyPadding);
+    assert_eq!(found[0].path.as_deref(), Some("x"));
+
+    // int8: -1 extendido en signo es canónico; 0xff sin extender, y 0x7f con
+    // relleno de unos, no
+    let int8 = selector("f", ParamType::Int(8));
+    let abi = "function f(int8 x)";
+    assert!(findings(abi, &format!("0x{}{}", int8, "ff".repeat(32))).is_empty());
+    for word in [
+        format!("{}ff", "00".repeat(31)),
+        format!("{}7f", "ff".repeat(31)),
+    ] {
+        let found = findings(abi, &format!("0x{}{}", int8, word));
+        assert_eq!(found.len(), 1, "{}", word);
+        assert_eq!(found[0].kind, FindingKind::DirtyPadding);
+    }
+}
+
+#[test]
+fn offset_outside_call_data_is_reported() {
+    // ethabi no decodifica un offset fuera de rango: se comprueba con los
+    // tokens que tendría la llamada
+    let contract = load_contract(&parse_abi_str("function f(bytes b)").unwrap()).unwrap();
+    let function = contract.function("f").unwrap();
+    let call_data = format!("0x{}{}", selector("f", ParamType::Bytes), word(0x1000));
+    let found = check_encoding(function, &[Token::Bytes(vec![0xab])], &call_data).unwrap();
+
+    let kinds: Vec<_> = found.iter().map(|f| f.kind).collect();
+    assert_eq!(
+        kinds,
+        [FindingKind::NonCanonicalOffset, FindingKind::MissingBytes]
+    );
+    assert!(found[0].detail.contains("fuera"));
+}
+
+#[test]
+fn findings_are_capped_including_the_trailing_one() {
+    let elements: String = (0..40).map(|_| format!("{}01", "ff".repeat(31))).collect();
+    let call_data = format!(
+        "0x{}{}{}{}deadbeef",
+        selector("f", ParamType::Array(Box::new(ParamType::Uint(8)))),
+        word(0x20),
+        word(40),
+        elements
+    );
+    let found = findings("function f(uint8[] xs)", &call_data);
+
+    assert_eq!(found.len(), 32);
+    assert_eq!(found[30].kind, FindingKind::DirtyPadding);
+    assert_eq!(found[31].kind, FindingKind::ExtraBytes);
+}
+
+// Un ancho imposible no debe llegar a `integer_fits`: ethabi lo acepta al
+// leer el tipo, pero la función se rechaza como ABI no válido
+#[test]
+fn impossible_integer_widths_are_invalid_abi() {
+    let function: Function = serde_json::from_value(json!({
+        "type": "function",
+        "name": "f",
+        "inputs": [{"name": "x", "type": "int264"}],
+        "outputs": [],
+        "stateMutability": "nonpayable",
+    }))
+    .unwrap();
+    let call_data = format!("0x{}{}", selector("f", ParamType::Int(264)), word(1));
+    let Err(err) = check_encoding(&function, &[Token::Int(1.into())], &call_data) else {
+        panic!("int264 debería rechazarse");
+    };
+    assert_eq!(err.code(), "INVALID_ABI");
+}
+
+#[cfg(feature = "server")]
+#[actix_web::test]
+async fn strict_decode_with_uint512_abi_is_rejected() {
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use actix_web::{web, App};
+    use rust_decompile_test::abi::MemoryAbiCache;
+    use rust_decompile_test::history::HistoryStore;
+    use rust_decompile_test::{handlers, AppConfig, Decoder};
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(AppConfig::default()))
+            .app_data(web::Data::new(Decoder::new(MemoryAbiCache::new())))
+            .app_data(web::Data::new(HistoryStore::open_in_memory().unwrap()))
+            .configure(handlers::configure),
+    )
+    .await;
+    let req = TestRequest::post()
+        .uri("/decode")
+        .set_json(json!({
+            "call_data": format!("0x{}{}", selector("f", ParamType::Uint(512)), word(1)),
+            "abi": [{
+                "type": "function",
+                "name": "f",
+                "inputs": [{"name": "x", "type": "uint512"}],
+                "outputs": [],
+                "stateMutability": "nonpayable",
+            }],
+            "strict": true,
+        }))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), 422);
+    let body: serde_json::Value = read_body_json(resp).await;
+    assert_eq!(body["code"], "INVALID_ABI");
+}
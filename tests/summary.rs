@@ -0,0 +1,152 @@
+// Resúmenes deterministas: plantillas por firma, con y sin metadatos del token.
+use ethabi::ethereum_types::U256;
+use ethabi::{Address, Contract, Token};
+use rust_decompile_test::decoder::load_contract;
+use rust_decompile_test::human_abi::parse_abi_str;
+use rust_decompile_test::summary::{
+    annotate_arguments, format_units, short_address, summarize, token_amounts,
+};
+use rust_decompile_test::{TokenMetadata, TokenStandard};
+
+const ABI: &str = "
+    function transfer(address to, uint256 amount) returns (bool)
+    function approve(address spender, uint256 amount) returns (bool)
+    function transferFrom(address from, address to, uint256 amount) returns (bool)
+    function grantRole(bytes32 role, address account)
+    function swap(uint256 amountIn, uint256 minOut)
+";
+
+fn contract() -> Contract {
+    load_contract(&parse_abi_str(ABI).unwrap()).unwrap()
+}
+
+fn spender() -> Address {
+    "742d35cc6634c0532925a3b844bc454e4438f44e".parse().unwrap()
+}
+
+fn usdc() -> TokenMetadata {
+    TokenMetadata {
+        standard: Some(TokenStandard::Erc20),
+        name: Some("USD Coin".to_string()),
+        symbol: Some("USDC".to_string()),
+        decimals: Some(6),
+    }
+}
+
+fn collection() -> TokenMetadata {
+    TokenMetadata {
+        standard: Some(TokenStandard::Erc721),
+        name: Some("Bored Ape Yacht Club".to_string()),
+        symbol: Some("BAYC".to_string()),
+        decimals: None,
+    }
+}
+
+#[test]
+fn transfers_use_token_units_when_metadata_is_known() {
+    let contract = contract();
+    let transfer = contract.function("transfer").unwrap();
+    let tokens = [
+        Token::Address(spender()),
+        Token::Uint(U256::from(1_500_000)),
+    ];
+
+    assert_eq!(
+        summarize(transfer, &tokens, Some(&usdc())).as_deref(),
+        Some("Transferir 1.5 USDC a 0x742d…f44e")
+    );
+    assert_eq!(
+        summarize(transfer, &tokens, None).as_deref(),
+        Some("Transferir 1500000 unidades base a 0x742d…f44e")
+    );
+}
+
+#[test]
+fn approvals_flag_unlimited_and_revoked_allowances() {
+    let contract = contract();
+    let approve = contract.function("approve").unwrap();
+
+    let unlimited = [Token::Address(spender()), Token::Uint(U256::MAX)];
+    assert_eq!(
+        summarize(approve, &unlimited, Some(&usdc())).as_deref(),
+        Some("Aprobar a 0x742d…f44e para gastar USDC ILIMITADO")
+    );
+
+    let revoked = [Token::Address(spender()), Token::Uint(U256::zero())];
+    assert_eq!(
+        summarize(approve, &revoked, None).as_deref(),
+        Some("Revocar la aprobación de 0x742d…f44e sobre el token")
+    );
+
+    let grant = contract.function("grantRole").unwrap();
+    let tokens = [Token::FixedBytes(vec![0; 32]), Token::Address(spender())];
+    assert_eq!(
+        summarize(grant, &tokens, None).as_deref(),
+        Some("Conceder el rol DEFAULT_ADMIN_ROLE a 0x742d…f44e")
+    );
+}
+
+#[test]
+fn token_amounts_are_annotated_in_units() {
+    let contract = contract();
+    let transfer = contract.function("transfer").unwrap();
+    let tokens = [
+        Token::Address(spender()),
+        Token::Uint(U256::from(1_500_000)),
+    ];
+
+    let amounts = token_amounts(transfer, &tokens, Some(&usdc()));
+    assert_eq!(amounts.len(), 1);
+    assert_eq!(amounts[0].index, 1);
+    assert_eq!(amounts[0].argument, "amount");
+    assert_eq!(amounts[0].raw, "1500000");
+    assert_eq!(amounts[0].formatted, "1.5 USDC");
+    assert!(token_amounts(transfer, &tokens, None).is_empty());
+
+    let arguments = ["to".to_string(), "Uint(1500000)".to_string()];
+    assert_eq!(
+        annotate_arguments(&arguments, &amounts),
+        ["to", "Uint(1500000) = 1.5 USDC"]
+    );
+}
+
+#[test]
+fn erc721_ids_are_not_amounts() {
+    let contract = contract();
+    let transfer_from = contract.function("transferFrom").unwrap();
+    let tokens = [
+        Token::Address(spender()),
+        Token::Address(Address::repeat_byte(0x11)),
+        Token::Uint(U256::from(42)),
+    ];
+
+    assert!(token_amounts(transfer_from, &tokens, Some(&collection())).is_empty());
+    assert_eq!(
+        summarize(transfer_from, &tokens, Some(&collection())).as_deref(),
+        Some("Transferir el NFT BAYC #42 de 0x742d…f44e a 0x1111…1111")
+    );
+
+    let approve = contract.function("approve").unwrap();
+    let tokens = [Token::Address(spender()), Token::Uint(U256::from(42))];
+    assert_eq!(
+        summarize(approve, &tokens, Some(&collection())).as_deref(),
+        Some("Aprobar a 0x742d…f44e para transferir el NFT BAYC #42")
+    );
+}
+
+#[test]
+fn unknown_signatures_have_no_summary() {
+    let contract = contract();
+    let swap = contract.function("swap").unwrap();
+    let tokens = [Token::Uint(U256::one()), Token::Uint(U256::one())];
+    assert_eq!(summarize(swap, &tokens, None), None);
+}
+
+#[test]
+fn formats_units_and_addresses() {
+    assert_eq!(format_units(&U256::from(1_500_000), 6), "1.5");
+    assert_eq!(format_units(&U256::from(5), 18), "0.000000000000000005");
+    assert_eq!(format_units(&U256::from(42), 0), "42");
+    assert_eq!(format_units(&U256::from(3_000_000), 6), "3");
+    assert_eq!(short_address(&spender()), "0x742d…f44e");
+}
@@ -0,0 +1,204 @@
+// Metadatos de tokens contra un nodo JSON-RPC mínimo en proceso: consultas en
+// paralelo con plazo total y caché también de los contratos que no son tokens.
+#![cfg(feature = "explorer")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use ethabi::{Address, Token};
+use reqwest::Client;
+use rust_decompile_test::error::AppError;
+use rust_decompile_test::store::{CacheStore, FsStore};
+use rust_decompile_test::token::TokenResolver;
+use rust_decompile_test::upstream::Upstream;
+use rust_decompile_test::{ChainConfig, RetryPolicy, TokenStandard};
+use serde_json::{json, Value};
+
+const CHAIN_ID: u64 = 421614;
+
+#[derive(Clone, Copy)]
+enum Node {
+    Erc20,     // USDC con 6 decimales
+    NotAToken, // Revierte en todas las funciones
+    Slow,      // Responde después del plazo
+}
+
+// Servidor HTTP de un solo uso por conexión; cuenta los eth_call recibidos
+fn spawn_node(node: Node) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let counter = counter.clone();
+            thread::spawn(move || serve(stream, node, &counter));
+        }
+    });
+    (url, calls)
+}
+
+fn serve(mut stream: TcpStream, node: Node, calls: &AtomicUsize) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let line = line.trim_end().to_lowercase();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("content-length:") {
+            length = value.trim().parse().unwrap();
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    let request: Value = serde_json::from_slice(&body).unwrap();
+    calls.fetch_add(1, Ordering::SeqCst);
+
+    let data = request["params"][0]["data"].as_str().unwrap();
+    let result = match (node, &data[..10]) {
+        (Node::Slow, _) => {
+            thread::sleep(Duration::from_secs(2));
+            None
+        }
+        (Node::Erc20, "0x313ce567") => Some(ethabi::encode(&[Token::Uint(6.into())])),
+        (Node::Erc20, "0x95d89b41") => Some(ethabi::encode(&[Token::String("USDC".into())])),
+        (Node::Erc20, "0x06fdde03") => Some(ethabi::encode(&[Token::String("USD Coin".into())])),
+        _ => None,
+    };
+    let response = match result {
+        Some(data) => json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": format!("0x{}", hex::encode(data)),
+        }),
+        None => json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "error": { "code": 3, "message": "execution reverted" },
+        }),
+    }
+    .to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.len(),
+        response
+    );
+}
+
+fn resolver(rpc_url: String, dir: &std::path::Path, deadline: Duration) -> TokenResolver {
+    let chains = vec![ChainConfig {
+        chain_id: CHAIN_ID,
+        name: "Arbitrum Sepolia".to_string(),
+        explorer_api_url: "http://127.0.0.1:9".to_string(),
+        api_key_env: None,
+        rpc_url: Some(rpc_url),
+    }];
+    let policy = RetryPolicy {
+        max_attempts: 1,
+        initial_backoff_ms: 10,
+        max_backoff_ms: 10,
+        breaker_threshold: 100,
+        breaker_cooldown_secs: 1,
+    };
+    let upstream = Upstream::new(
+        "rpc",
+        Client::new(),
+        Duration::from_secs(5),
+        policy,
+        AppError::RpcUnavailable,
+    );
+    TokenResolver::new(
+        chains,
+        Arc::new(upstream),
+        Box::new(FsStore::new(dir)),
+        deadline,
+    )
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rdt-token-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn token_address() -> Address {
+    Address::repeat_byte(0xaa)
+}
+
+#[tokio::test]
+async fn reads_and_caches_erc20_metadata() {
+    let (url, calls) = spawn_node(Node::Erc20);
+    let dir = temp_dir("erc20");
+    let resolver = resolver(url, &dir, Duration::from_secs(3));
+
+    let token = resolver
+        .resolve(CHAIN_ID, &token_address())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token.standard, Some(TokenStandard::Erc20));
+    assert_eq!(token.symbol.as_deref(), Some("USDC"));
+    assert_eq!(token.name.as_deref(), Some("USD Coin"));
+    assert_eq!(token.decimals, Some(6));
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+    let cached = resolver.resolve(CHAIN_ID, &token_address()).await.unwrap();
+    assert_eq!(cached.and_then(|token| token.decimals), Some(6));
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn contracts_that_are_not_tokens_are_cached_too() {
+    let (url, calls) = spawn_node(Node::NotAToken);
+    let dir = temp_dir("not-a-token");
+    let resolver = resolver(url, &dir, Duration::from_secs(3));
+
+    assert!(resolver
+        .resolve(CHAIN_ID, &token_address())
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+    assert!(resolver
+        .resolve(CHAIN_ID, &token_address())
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+    // Sin rpc_url en la cadena no se llama al nodo
+    assert!(resolver
+        .resolve(1, &token_address())
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn slow_nodes_hit_the_overall_deadline_and_are_not_cached() {
+    let (url, _) = spawn_node(Node::Slow);
+    let dir = temp_dir("slow");
+    let resolver = resolver(url, &dir, Duration::from_millis(300));
+
+    let started = std::time::Instant::now();
+    let Err(error) = resolver.resolve(CHAIN_ID, &token_address()).await else {
+        panic!("el plazo debería haberse agotado");
+    };
+    assert_eq!(error.code(), "RPC_UNAVAILABLE");
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(FsStore::new(&dir).keys().unwrap().is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,28 @@
+// Ejecutar con: wasm-pack test --node -- --no-default-features --features wasm
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use rust_decompile_test::wasm::{decode_call, selector};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const STORAGE_ABI: &str = r#"[
+    {"inputs":[],"name":"retrieve","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},
+    {"inputs":[{"internalType":"uint256","name":"num","type":"uint256"}],"name":"store","outputs":[],"stateMutability":"nonpayable","type":"function"}
+]"#;
+
+#[wasm_bindgen_test]
+fn computes_selector() {
+    assert_eq!(selector("store(uint256)").unwrap(), "0x6057361d");
+}
+
+#[wasm_bindgen_test]
+fn decodes_call_with_typed_arguments() {
+    let call_data = "0x6057361d0000000000000000000000000000000000000000000000000000000000000003";
+    let decoded: serde_json::Value =
+        serde_wasm_bindgen::from_value(decode_call(STORAGE_ABI, call_data).unwrap()).unwrap();
+
+    assert_eq!(decoded["function_name"], "store");
+    assert_eq!(decoded["signature"], "store(uint256)");
+    assert_eq!(decoded["arguments"][0]["name"], "num");
+    assert_eq!(decoded["arguments"][0]["type"], "uint256");
+    assert_eq!(decoded["arguments"][0]["value"], "3");
+}